@@ -16,6 +16,9 @@ pub struct Def {
     pub array_size: i32,
 
     pub tag: String,
+
+    /// Doc comment written above (or after) the declaration.
+    pub comment: String,
 }
 
 #[derive(Debug, Default, Clone, Serialize)]
@@ -25,6 +28,8 @@ pub struct Struct {
     pub props: Vec<Def>,
 
     pub tag: String,
+
+    pub comment: String,
 }
 
 #[derive(Debug, Default, Clone, Serialize)]
@@ -32,6 +37,8 @@ pub struct EnumValue {
     pub name: String,
 
     pub index: i32,
+
+    pub comment: String,
 }
 
 #[derive(Debug, Default, Clone, Serialize)]
@@ -39,6 +46,8 @@ pub struct Enum {
     pub name: String,
 
     pub values: Vec<EnumValue>,
+
+    pub comment: String,
 }
 
 #[derive(Debug, Default, Clone, Serialize)]
@@ -55,6 +64,8 @@ pub struct Union {
     pub name: String,
 
     pub switch: Switch,
+
+    pub comment: String,
 }
 
 #[derive(Debug, Default, Clone, Serialize)]
@@ -62,6 +73,8 @@ pub struct Case {
     pub value: String,
 
     pub ret_type: Def,
+
+    pub comment: String,
 }
 
 #[derive(Debug, Default, Clone, Serialize)]
@@ -82,6 +95,71 @@ pub struct Namespace {
     pub structs: Vec<Struct>,
 }
 
+/// Collects the comments between declarations so each declaration gets the
+/// block of comment lines directly above it. A comment on the same line as
+/// the end of the previous declaration documents that declaration instead.
+#[derive(Default)]
+struct Comments {
+    lines: Vec<String>,
+    end_line: usize,
+    decl_line: usize,
+}
+
+fn comment_text(node: &Pair<Rule>) -> Option<String> {
+    let raw = node.as_str();
+    match node.as_rule() {
+        Rule::line_comment if raw.starts_with("//") => Some(raw.trim_start_matches('/').trim().to_string()),
+        Rule::long_comment => {
+            let lines: Vec<&str> = raw[2..raw.len() - 2]
+                .lines()
+                .map(|line| line.trim().trim_start_matches('*').trim())
+                .collect();
+            Some(lines.join("\n").trim().to_string())
+        }
+        _ => None,
+    }
+}
+
+impl Comments {
+    /// Records a comment, returning its text when it trails the previous
+    /// declaration on the same line.
+    fn push(&mut self, node: Pair<Rule>) -> Option<String> {
+        let start = node.as_span().start_pos().line_col().0;
+        let end = node.as_span().end_pos().line_col().0;
+        let text = comment_text(&node)?;
+        if start == self.decl_line {
+            return Some(text);
+        }
+        if start > self.end_line + 1 {
+            self.lines.clear();
+        }
+        self.lines.push(text);
+        self.end_line = end;
+        None
+    }
+
+    /// Takes the comment block ending on the line above (or on the first line
+    /// of) a declaration.
+    fn take(&mut self, node: &Pair<Rule>) -> String {
+        let start = node.as_span().start_pos().line_col().0;
+        self.decl_line = node.as_span().end_pos().line_col().0;
+        let lines = std::mem::take(&mut self.lines);
+        match self.end_line + 1 >= start {
+            true => lines.join("\n").trim().to_string(),
+            false => String::new(),
+        }
+    }
+}
+
+/// Sets a comment trailing a declaration unless it already has one.
+fn trail(comment: Option<&mut String>, text: Option<String>) {
+    if let (Some(comment), Some(text)) = (comment, text) {
+        if comment.is_empty() {
+            *comment = text;
+        }
+    }
+}
+
 fn name_from_bracket_start(bs: Pair<Rule>) -> Result<String, &'static str> {
     for node in bs.into_inner() {
         if node.as_rule() == Rule::identifier {
@@ -102,7 +180,7 @@ fn ident_from_tag(tag: Pair<Rule>) -> Result<String, &'static str> {
 
 fn get_array_info(d: Pair<Rule>) -> Result<(bool, i32), &'static str> {
     let mut fixed_array: bool = false;
-    let mut array_size: i32 = std::i32::MAX;
+    let mut array_size: i32 = i32::MAX;
     for node in d.into_inner() {
         match node.as_rule() {
             Rule::var_array => {
@@ -152,32 +230,31 @@ fn build_def(d: Pair<Rule>) -> Result<Def, &'static str> {
     }
 
     Ok(Def {
-        name: name,
-        type_name: type_name,
-        fixed_array: fixed_array,
-        array_size: array_size,
-        tag: tag,
+        name,
+        type_name,
+        fixed_array,
+        array_size,
+        tag,
+        comment: String::new(),
     })
 }
 
 fn build_typedef(td: Pair<Rule>) -> Result<Typedef, &'static str> {
     let mut def = Def::default();
     for node in td.into_inner() {
-        match node.as_rule() {
-            Rule::type_decl => {
-                let built_def = build_def(node)?;
-                def = built_def;
-            }
-            _ => {}
+        if node.as_rule() == Rule::type_decl {
+            let built_def = build_def(node)?;
+            def = built_def;
         }
     }
-    Ok(Typedef { def: def })
+    Ok(Typedef { def })
 }
 
 fn build_struct(st: Pair<Rule>) -> Result<Struct, &'static str> {
     let mut name: String = "".to_string();
     let mut tag: String = "".to_string();
     let mut props: Vec<Def> = Vec::new();
+    let mut comments = Comments::default();
     for node in st.into_inner() {
         match node.as_rule() {
             Rule::tag => tag = ident_from_tag(node)?,
@@ -185,17 +262,22 @@ fn build_struct(st: Pair<Rule>) -> Result<Struct, &'static str> {
                 name = name_from_bracket_start(node)?;
             }
             Rule::type_decl => {
+                let comment = comments.take(&node);
                 let decl = build_def(node)?;
-                props.push(decl);
+                props.push(Def { comment, ..decl });
+            }
+            Rule::line_comment | Rule::long_comment => {
+                trail(props.last_mut().map(|prop| &mut prop.comment), comments.push(node));
             }
             _ => {}
         }
     }
 
     Ok(Struct {
-        name: name,
-        props: props,
-        tag: tag,
+        name,
+        props,
+        tag,
+        comment: String::new(),
     })
 }
 
@@ -215,50 +297,64 @@ fn build_enum_val(en: Pair<Rule>) -> Result<EnumValue, &'static str> {
     }
 
     Ok(EnumValue {
-        name: name,
-        index: index,
+        name,
+        index,
+        comment: String::new(),
     })
 }
 
 fn build_enum(en: Pair<Rule>) -> Result<Enum, &'static str> {
     let mut name: String = "".to_string();
     let mut values: Vec<EnumValue> = Vec::new();
+    let mut comments = Comments::default();
     for node in en.into_inner() {
         match node.as_rule() {
             Rule::bracket_start => {
                 name = name_from_bracket_start(node)?;
             }
             Rule::enum_decl => {
+                let comment = comments.take(&node);
                 let val = build_enum_val(node)?;
-                values.push(val);
+                values.push(EnumValue { comment, ..val });
+            }
+            Rule::line_comment | Rule::long_comment => {
+                trail(values.last_mut().map(|val| &mut val.comment), comments.push(node));
             }
             _ => {}
         }
     }
 
     Ok(Enum {
-        name: name,
-        values: values,
+        name,
+        values,
+        comment: String::new(),
     })
 }
 
 fn build_case(ca: Pair<Rule>) -> Result<Case, &'static str> {
     let mut value: String = "".to_string();
     let mut def = Def::default();
+    let mut comments = Comments::default();
     for node in ca.into_inner() {
         match node.as_rule() {
             Rule::identifier => {
                 value = node.as_str().to_string();
+                comments.take(&node);
             }
             Rule::type_decl => {
-                def = build_def(node)?;
+                let comment = comments.take(&node);
+                def = Def { comment, ..build_def(node)? };
+            }
+            Rule::line_comment | Rule::long_comment => {
+                trail(Some(&mut def.comment), comments.push(node));
             }
             _ => {}
         }
     }
     Ok(Case {
-        value: value,
+        value,
         ret_type: def,
+        comment: String::new(),
     })
 }
 
@@ -266,6 +362,7 @@ fn build_switch(sw: Pair<Rule>) -> Result<Switch, &'static str> {
     let mut enum_name: String = "".to_string();
     let mut enum_type: String = "".to_string();
     let mut cases: Vec<Case> = Vec::new();
+    let mut comments = Comments::default();
     for node in sw.into_inner() {
         match node.as_rule() {
             Rule::single_param => {
@@ -274,17 +371,21 @@ fn build_switch(sw: Pair<Rule>) -> Result<Switch, &'static str> {
                 enum_name = type_id.1;
             }
             Rule::case_statement => {
+                let comment = comments.take(&node);
                 let cas = build_case(node)?;
-                cases.push(cas);
+                cases.push(Case { comment, ..cas });
+            }
+            Rule::line_comment | Rule::long_comment => {
+                comments.push(node);
             }
             _ => {}
         }
     }
 
     Ok(Switch {
-        enum_name: enum_name,
-        enum_type: enum_type,
-        cases: cases,
+        enum_name,
+        enum_type,
+        cases,
     })
 }
 
@@ -324,8 +425,9 @@ fn build_union(un: Pair<Rule>) -> Result<Union, &'static str> {
     }
 
     Ok(Union {
-        name: name,
-        switch: switch,
+        name,
+        switch,
+        comment: String::new(),
     })
 }
 
@@ -335,54 +437,127 @@ fn build_namespace(ns: Pair<Rule>) -> Result<Namespace, &'static str> {
     let mut structs: Vec<Struct> = Vec::new();
     let mut enums: Vec<Enum> = Vec::new();
     let mut unions: Vec<Union> = Vec::new();
+    let mut comments = Comments::default();
     for node in ns.into_inner() {
         match node.as_rule() {
             Rule::bracket_start => {
                 name = name_from_bracket_start(node)?;
             }
             Rule::typedef => {
-                let def = build_typedef(node)?;
+                let comment = comments.take(&node);
+                let mut def = build_typedef(node)?;
+                def.def.comment = comment;
                 typedefs.push(def)
             }
             Rule::Struct => {
+                let comment = comments.take(&node);
                 let stru = build_struct(node)?;
-                structs.push(stru);
+                structs.push(Struct { comment, ..stru });
             }
             Rule::Enum => {
+                let comment = comments.take(&node);
                 let enu = build_enum(node)?;
-                enums.push(enu);
+                enums.push(Enum { comment, ..enu });
             }
             Rule::union => {
+                let comment = comments.take(&node);
                 let uni = build_union(node)?;
-                unions.push(uni);
+                unions.push(Union { comment, ..uni });
+            }
+            Rule::line_comment | Rule::long_comment => {
+                comments.push(node);
             }
             _ => {}
         }
     }
 
     Ok(Namespace {
-        name: name,
-        typedefs: typedefs,
-        structs: structs,
-        enums: enums,
-        unions: unions,
+        name,
+        typedefs,
+        structs,
+        enums,
+        unions,
     })
 }
 
 pub fn build_namespaces(raw_idl: String) -> Result<Vec<Namespace>, &'static str> {
     let mut namespaces: Vec<Namespace> = Vec::new();
     let file = XDRParser::parse(Rule::file, &raw_idl)
-        .expect("unsuccessful parse") // unwrap the parse result
+        .map_err(|_| "unsuccessful parse")?
         .next()
         .unwrap();
     for node in file.into_inner() {
-        match node.as_rule() {
-            Rule::namespace => {
-                let namespace = build_namespace(node)?;
-                namespaces.push(namespace);
-            }
-            _ => {}
+        if node.as_rule() == Rule::namespace {
+            let namespace = build_namespace(node)?;
+            namespaces.push(namespace);
         }
     }
     Ok(namespaces)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments() {
+        let namespaces = build_namespaces(String::from(
+            "%#include \"common.x\"
+
+namespace test
+{
+    // Detached comment.
+
+    // Hash of a block.
+    typedef opaque Hash[32];
+
+    /*
+     * A kind of item.
+     */
+    enum Kind
+    {
+        // The first kind.
+        A = 0,
+        B = 1 // The second kind.
+    };
+
+    // An item.
+    // Spans two lines.
+    struct Item
+    {
+        // Item hash.
+        Hash hash;
+
+        int count; // Number of items.
+        int other;
+    };
+
+    union Body switch (Kind Type)
+    {
+        // Selected by A.
+        case A:
+            // The item.
+            Item item;
+        case B:
+            void;
+    }
+}",
+        ))
+        .unwrap();
+        let ns = &namespaces[0];
+        assert_eq!(ns.typedefs[0].def.comment, "Hash of a block.");
+        assert_eq!(ns.enums[0].comment, "A kind of item.");
+        assert_eq!(ns.enums[0].values[0].comment, "The first kind.");
+        assert_eq!(ns.enums[0].values[1].comment, "The second kind.");
+        assert_eq!(ns.structs[0].comment, "An item.\nSpans two lines.");
+        let props = &ns.structs[0].props;
+        assert_eq!(props[0].comment, "Item hash.");
+        assert_eq!(props[1].comment, "Number of items.");
+        assert_eq!(props[2].comment, "");
+        let cases = &ns.unions[0].switch.cases;
+        assert_eq!(ns.unions[0].comment, "");
+        assert_eq!(cases[0].comment, "Selected by A.");
+        assert_eq!(cases[0].ret_type.comment, "The item.");
+        assert_eq!(cases[1].comment, "");
+    }
+}
@@ -0,0 +1,502 @@
+use super::ast::*;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Whether a change alters the encoding of values valid under the old schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Breaking,
+    Safe,
+}
+
+/// One difference between two versions of a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub severity: Severity,
+    /// Type, and field, value or arm the change applies to: `ns.Type.field`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Breaking => "breaking",
+            Severity::Safe => "safe",
+        };
+        write!(f, "{}: {}: {}", severity, self.path, self.message)
+    }
+}
+
+enum Decl<'a> {
+    Typedef(&'a Def),
+    Struct(&'a Struct),
+    Enum(&'a Enum),
+    Union(&'a Union),
+}
+
+impl<'a> Decl<'a> {
+    fn kind(&self) -> &'static str {
+        match self {
+            Decl::Typedef(_) => "typedef",
+            Decl::Struct(_) => "struct",
+            Decl::Enum(_) => "enum",
+            Decl::Union(_) => "union",
+        }
+    }
+}
+
+/// Every type by name, with its qualified path. Namespaces are not part of the
+/// encoding, so a type may move between them freely.
+fn types(namespaces: &[Namespace]) -> Vec<(String, String, Decl<'_>)> {
+    let mut types = Vec::new();
+    for ns in namespaces {
+        let path = |name: &str| format!("{}.{}", ns.name, name);
+        for t in &ns.typedefs {
+            types.push((t.def.name.clone(), path(&t.def.name), Decl::Typedef(&t.def)));
+        }
+        for s in &ns.structs {
+            types.push((s.name.clone(), path(&s.name), Decl::Struct(s)));
+        }
+        for e in &ns.enums {
+            types.push((e.name.clone(), path(&e.name), Decl::Enum(e)));
+        }
+        for u in &ns.unions {
+            types.push((u.name.clone(), path(&u.name), Decl::Union(u)));
+        }
+    }
+    types
+}
+
+/// Readable form of a declaration's type, e.g. `opaque[32]` or `string<>`.
+fn describe(def: &Def) -> String {
+    if def.name.is_empty() {
+        return String::from("void");
+    }
+    match (def.fixed_array, def.array_size) {
+        (true, size) => format!("{}[{}]", def.type_name, size),
+        (false, 0) => def.type_name.clone(),
+        (false, 2147483647) => format!("{}<>", def.type_name),
+        (false, size) => format!("{}<{}>", def.type_name, size),
+    }
+}
+
+/// Whether two declarations encode alike, up to the bound of variable length
+/// arrays.
+fn same_type(old: &Def, new: &Def) -> bool {
+    let bounded = |def: &Def| !def.fixed_array && def.array_size != 0;
+    old.type_name == new.type_name
+        && old.fixed_array == new.fixed_array
+        && bounded(old) == bounded(new)
+        && (!old.fixed_array || old.array_size == new.array_size)
+}
+
+/// Enums of a schema by name.
+fn enums(namespaces: &[Namespace]) -> Enums<'_> {
+    namespaces
+        .iter()
+        .flat_map(|ns| &ns.enums)
+        .map(|e| (e.name.as_str(), e))
+        .collect()
+}
+
+/// Discriminant of a union case: the number of its enum value, or the case
+/// itself when it does not name one.
+fn discriminant(enums: &Enums, un: &Union, case: &str) -> String {
+    enums
+        .get(un.switch.enum_type.as_str())
+        .and_then(|e| e.values.iter().find(|v| v.name == case))
+        .map_or_else(|| case.to_string(), |v| v.index.to_string())
+}
+
+type Enums<'a> = HashMap<&'a str, &'a Enum>;
+
+/// Arm of `to` matching a case of `from`: the case of the same name, or the
+/// case of the same discriminant when the enum value was renamed.
+fn arm<'u>(from: (&Enums, &Union), to: (&Enums, &'u Union), case: &str) -> Option<&'u Def> {
+    let cases = &to.1.switch.cases;
+    let found = cases.iter().find(|c| c.value == case).or_else(|| {
+        let value = discriminant(from.0, from.1, case);
+        cases.iter().find(|c| {
+            discriminant(to.0, to.1, &c.value) == value && !from.1.switch.cases.iter().any(|f| f.value == c.value)
+        })
+    });
+    found.map(|c| &c.ret_type)
+}
+
+struct Report<'a> {
+    changes: Vec<Change>,
+    old_enums: Enums<'a>,
+    new_enums: Enums<'a>,
+}
+
+impl<'a> Report<'a> {
+    fn push(&mut self, severity: Severity, path: &str, message: String) {
+        self.changes.push(Change {
+            severity,
+            path: path.to_string(),
+            message,
+        });
+    }
+
+    /// Compares the types of two declarations at the same position.
+    fn decl(&mut self, path: &str, old: &Def, new: &Def) {
+        if !same_type(old, new) {
+            self.push(
+                Severity::Breaking,
+                path,
+                format!("type changed from {} to {}", describe(old), describe(new)),
+            );
+        } else if new.array_size < old.array_size {
+            self.push(
+                Severity::Breaking,
+                path,
+                format!("bound shrunk from {} to {}", describe(old), describe(new)),
+            );
+        } else if new.array_size > old.array_size {
+            self.push(
+                Severity::Safe,
+                path,
+                format!("bound relaxed from {} to {}", describe(old), describe(new)),
+            );
+        }
+    }
+
+    /// Fields are encoded by position, so a field keeping its position and
+    /// type under a name the old struct did not use is a rename.
+    fn structure(&mut self, path: &str, old: &Struct, new: &Struct) {
+        let position = |s: &Struct, name: &str| s.props.iter().position(|p| p.name == name);
+        let renamed = |i: usize| match (old.props.get(i), new.props.get(i)) {
+            (Some(prop), Some(next)) => {
+                position(new, &prop.name).is_none() && position(old, &next.name).is_none() && same_type(prop, next)
+            }
+            _ => false,
+        };
+        for (i, prop) in old.props.iter().enumerate() {
+            let field = format!("{}.{}", path, prop.name);
+            match position(new, &prop.name) {
+                None if renamed(i) => {
+                    let next = &new.props[i];
+                    self.push(Severity::Safe, &field, format!("field renamed to {}", next.name));
+                    self.decl(&field, prop, next);
+                }
+                None => self.push(Severity::Breaking, &field, String::from("field removed")),
+                Some(j) if i != j => self.push(
+                    Severity::Breaking,
+                    &field,
+                    format!("field moved from position {} to {}", i + 1, j + 1),
+                ),
+                Some(j) => self.decl(&field, prop, &new.props[j]),
+            }
+        }
+        for (i, prop) in new.props.iter().enumerate() {
+            if position(old, &prop.name).is_none() && !renamed(i) {
+                self.push(
+                    Severity::Breaking,
+                    &format!("{}.{}", path, prop.name),
+                    String::from("field added"),
+                );
+            }
+        }
+    }
+
+    /// Values are encoded by number, so a value keeping its number under a
+    /// name the old enum did not use is a rename.
+    fn enumeration(&mut self, path: &str, old: &Enum, new: &Enum) {
+        let find = |e: &Enum, name: &str| e.values.iter().find(|v| v.name == name).map(|v| v.index);
+        let renamed = |index: i32| {
+            let old_name = old.values.iter().find(|v| v.index == index).map(|v| v.name.as_str());
+            let new_name = new.values.iter().find(|v| v.index == index).map(|v| v.name.as_str());
+            match (old_name, new_name) {
+                (Some(a), Some(b)) => find(new, a).is_none() && find(old, b).is_none(),
+                _ => false,
+            }
+        };
+        for value in &old.values {
+            let member = format!("{}.{}", path, value.name);
+            match find(new, &value.name) {
+                None if renamed(value.index) => {
+                    let next = new.values.iter().find(|v| v.index == value.index).unwrap();
+                    self.push(
+                        Severity::Safe,
+                        &member,
+                        format!("value {} renamed to {}", value.index, next.name),
+                    );
+                }
+                None => self.push(Severity::Breaking, &member, format!("value {} removed", value.index)),
+                Some(index) if index != value.index => self.push(
+                    Severity::Breaking,
+                    &member,
+                    format!("value renumbered from {} to {}", value.index, index),
+                ),
+                Some(_) => {}
+            }
+        }
+        for value in &new.values {
+            if find(old, &value.name).is_none() && !renamed(value.index) {
+                self.push(
+                    Severity::Safe,
+                    &format!("{}.{}", path, value.name),
+                    format!("value {} added", value.index),
+                );
+            }
+        }
+    }
+
+    fn union(&mut self, path: &str, old: &Union, new: &Union) {
+        if old.switch.enum_type != new.switch.enum_type {
+            self.push(
+                Severity::Breaking,
+                path,
+                format!(
+                    "discriminant changed from {} to {}",
+                    old.switch.enum_type, new.switch.enum_type
+                ),
+            );
+            return;
+        }
+        for case in &old.switch.cases {
+            let member = format!("{}.{}", path, case.value);
+            match arm((&self.old_enums, old), (&self.new_enums, new), &case.value) {
+                None => self.push(Severity::Breaking, &member, String::from("arm removed")),
+                Some(ret) if case.ret_type.name.is_empty() != ret.name.is_empty() => self.push(
+                    Severity::Breaking,
+                    &member,
+                    format!("arm changed from {} to {}", describe(&case.ret_type), describe(ret)),
+                ),
+                Some(ret) => self.decl(&member, &case.ret_type, ret),
+            }
+        }
+        for case in &new.switch.cases {
+            if arm((&self.new_enums, new), (&self.old_enums, old), &case.value).is_none() {
+                self.push(Severity::Safe, &format!("{}.{}", path, case.value), String::from("arm added"));
+            }
+        }
+    }
+}
+
+/// Reports the differences between two versions of a schema, in the order of
+/// the old schema followed by new types. Any [`Severity::Breaking`] change means
+/// values encoded with one version may not decode with the other.
+pub fn check(old: &[Namespace], new: &[Namespace]) -> Vec<Change> {
+    let mut report = Report {
+        changes: Vec::new(),
+        old_enums: enums(old),
+        new_enums: enums(new),
+    };
+    let old_types = types(old);
+    let new_types = types(new);
+    let by_name: HashMap<&str, &(String, String, Decl)> = new_types.iter().map(|t| (t.0.as_str(), t)).collect();
+    for (name, path, old_decl) in &old_types {
+        let (_, path_new, new_decl) = match by_name.get(name.as_str()) {
+            Some(found) => found,
+            None => {
+                report.push(Severity::Breaking, path, format!("{} removed", old_decl.kind()));
+                continue;
+            }
+        };
+        if path != path_new {
+            report.push(Severity::Safe, path, format!("moved to {}", path_new));
+        }
+        match (old_decl, new_decl) {
+            (Decl::Typedef(a), Decl::Typedef(b)) => report.decl(path_new, a, b),
+            (Decl::Struct(a), Decl::Struct(b)) => report.structure(path_new, a, b),
+            (Decl::Enum(a), Decl::Enum(b)) => report.enumeration(path_new, a, b),
+            (Decl::Union(a), Decl::Union(b)) => report.union(path_new, a, b),
+            (a, b) => report.push(
+                Severity::Breaking,
+                path_new,
+                format!("changed from {} to {}", a.kind(), b.kind()),
+            ),
+        }
+    }
+    let old_names: Vec<&str> = old_types.iter().map(|t| t.0.as_str()).collect();
+    for (name, path, decl) in &new_types {
+        if !old_names.contains(&name.as_str()) {
+            report.push(Severity::Safe, path, format!("{} added", decl.kind()));
+        }
+    }
+    report.changes
+}
+
+/// Whether any change in a report breaks the wire format.
+pub fn is_breaking(changes: &[Change]) -> bool {
+    changes.iter().any(|c| c.severity == Severity::Breaking)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static OLD: &str = "namespace test
+{
+    enum Kind
+    {
+        CALL = 0,
+        DEPLOY = 1,
+        NONE = 2
+    };
+
+    struct Call
+    {
+        string function<32>;
+        opaque arguments<64>;
+        hyper nonce;
+        unsigned int retries;
+    };
+
+    typedef opaque Hash[32];
+
+    union Action switch (Kind Type)
+    {
+        case CALL:
+            Call call;
+        case DEPLOY:
+            Hash code;
+        case NONE:
+            void;
+    }
+}";
+
+    fn diff(old: &str, new: &str) -> Vec<String> {
+        let old = build_namespaces(String::from(old)).unwrap();
+        let new = build_namespaces(String::from(new)).unwrap();
+        check(&old, &new).iter().map(Change::to_string).collect()
+    }
+
+    fn changes(new: &str) -> Vec<String> {
+        diff(OLD, new)
+    }
+
+    #[test]
+    fn unchanged() {
+        assert!(changes(OLD).is_empty());
+    }
+
+    #[test]
+    fn safe() {
+        let new = OLD
+            .replace("NONE = 2", "NONE = 2,\n        UPGRADE = 3")
+            .replace("function<32>", "function<64>")
+            .replace(
+                "        case NONE:",
+                "        case UPGRADE:\n            Hash upgrade;\n        case NONE:",
+            )
+            .replace("    typedef", "    typedef int Height;\n\n    typedef");
+        let res = changes(&new);
+        assert_eq!(
+            res,
+            vec![
+                "safe: test.Call.function: bound relaxed from string<32> to string<64>",
+                "safe: test.Kind.UPGRADE: value 3 added",
+                "safe: test.Action.UPGRADE: arm added",
+                "safe: test.Height: typedef added",
+            ]
+        );
+        let old = build_namespaces(String::from(OLD)).unwrap();
+        let new = build_namespaces(new).unwrap();
+        assert!(!is_breaking(&check(&old, &new)));
+    }
+
+    #[test]
+    fn breaking() {
+        let new = OLD
+            .replace("DEPLOY = 1", "DEPLOY = 4")
+            .replace("arguments<64>", "arguments<16>")
+            .replace("        hyper nonce;\n", "")
+            .replace("unsigned int retries", "unsigned hyper retries")
+            .replace("Hash[32]", "Hash[20]")
+            .replace("        case NONE:\n            void;\n", "");
+        assert_eq!(
+            changes(&new),
+            vec![
+                "breaking: test.Hash: type changed from opaque[32] to opaque[20]",
+                "breaking: test.Call.arguments: bound shrunk from opaque<64> to opaque<16>",
+                "breaking: test.Call.nonce: field removed",
+                "breaking: test.Call.retries: field moved from position 4 to 3",
+                "breaking: test.Kind.DEPLOY: value renumbered from 1 to 4",
+                "breaking: test.Action.NONE: arm removed",
+            ]
+        );
+    }
+
+    #[test]
+    fn reorder_and_kind() {
+        let new = OLD
+            .replace(
+                "        string function<32>;\n        opaque arguments<64>;\n",
+                "        opaque arguments<64>;\n        string function<32>;\n",
+            )
+            .replace("unsigned int retries", "int retries")
+            .replace(
+                "typedef opaque Hash[32];",
+                "struct Hash\n    {\n        opaque data[32];\n    };",
+            )
+            .replace("namespace test", "namespace renamed");
+        assert_eq!(
+            changes(&new),
+            vec![
+                "safe: test.Hash: moved to renamed.Hash",
+                "breaking: renamed.Hash: changed from typedef to struct",
+                "safe: test.Call: moved to renamed.Call",
+                "breaking: renamed.Call.function: field moved from position 1 to 2",
+                "breaking: renamed.Call.arguments: field moved from position 2 to 1",
+                "breaking: renamed.Call.retries: type changed from unsigned int to int",
+                "safe: test.Kind: moved to renamed.Kind",
+                "safe: test.Action: moved to renamed.Action",
+            ]
+        );
+    }
+
+    #[test]
+    fn renames() {
+        let new = OLD
+            .replace("hyper nonce", "hyper sequence")
+            .replace("function<32>", "method<64>")
+            .replace("DEPLOY", "CREATE");
+        assert_eq!(
+            changes(&new),
+            vec![
+                "safe: test.Call.function: field renamed to method",
+                "safe: test.Call.function: bound relaxed from string<32> to string<64>",
+                "safe: test.Call.nonce: field renamed to sequence",
+                "safe: test.Kind.DEPLOY: value 1 renamed to CREATE",
+            ]
+        );
+    }
+
+    #[test]
+    fn enum_numbers() {
+        let new = OLD
+            .replace("CALL = 0", "CALL = 3")
+            .replace("NONE = 2", "NONE = 2,\n        CALL_V0 = 0");
+        assert_eq!(
+            changes(&new),
+            vec![
+                "breaking: test.Kind.CALL: value renumbered from 0 to 3",
+                "safe: test.Kind.CALL_V0: value 0 added",
+            ]
+        );
+    }
+
+    #[test]
+    fn swaps() {
+        let old = OLD.replace("hyper nonce", "unsigned int nonce");
+        let new = old
+            .replace("CALL = 0", "CALL = 1")
+            .replace("DEPLOY = 1", "DEPLOY = 0")
+            .replace(
+                "unsigned int nonce;\n        unsigned int retries;",
+                "unsigned int retries;\n        unsigned int nonce;",
+            );
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                "breaking: test.Call.nonce: field moved from position 3 to 4",
+                "breaking: test.Call.retries: field moved from position 4 to 3",
+                "breaking: test.Kind.CALL: value renumbered from 0 to 1",
+                "breaking: test.Kind.DEPLOY: value renumbered from 1 to 0",
+            ]
+        );
+    }
+}
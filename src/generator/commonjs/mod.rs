@@ -22,7 +22,7 @@ function _interopRequireDefault(obj) {
 static TYPEDEFS_T: &str = r#"
 // Start typedef section
 {{#each ns.typedefs as |td|}}
-exports.{{td.def.name}} = {{td.def.name}};
+{{js-doc td.def.comment null "name"}}exports.{{td.def.name}} = {{td.def.name}};
 function {{td.def.name}}() {
     return {{#typeconv td.def.name td.def.type_name td.def.array_size td.def.fixed_array}}{{/typeconv}};
 }
@@ -33,7 +33,7 @@ function {{td.def.name}}() {
 static STRUCTS_T: &str = r#"
 // Start struct section
 {{#each ns.structs as |st| ~}}
-exports.{{st.name}} = {{st.name}};
+{{js-doc st.comment st.props "name"}}exports.{{st.name}} = {{st.name}};
 function {{st.name}}() {
     return new _xdrJsSerialize.default.Struct(
         [{{#each st.props as |prop| ~}}"{{prop.name}}",{{/each ~}}],
@@ -47,7 +47,7 @@ function {{st.name}}() {
 static ENUM_T: &str = r#"
 // Start enum section
 {{#each ns.enums as |enum|}}
-exports.{{enum.name}} = {{enum.name}};
+{{js-doc enum.comment enum.values "name"}}exports.{{enum.name}} = {{enum.name}};
 function {{enum.name}}() {
     return new _xdrJsSerialize.default.Enum({
         {{#each enum.values as |val| ~}}
@@ -64,7 +64,7 @@ static UNION_T: &str = r#"
 // Start union section
 
 {{#each ns.unions as |uni|}}
-exports.{{uni.name}} = {{uni.name}};
+{{js-doc uni.comment uni.switch.cases "value"}}exports.{{uni.name}} = {{uni.name}};
 function {{uni.name}}() {
     return new _xdrJsSerialize.default.Union(
         {{uni.switch.enum_type}}(),
@@ -96,26 +96,14 @@ fn build_file_template() -> String {
 }
 
 fn is_array_type(def_type: &str) -> bool {
-    match def_type {
-        "Str" => true,
-        "opaque" => true,
-        _ => false,
-    }
+    matches!(def_type, "Str" | "opaque")
 }
 
 fn is_built_in(def_type: &str) -> bool {
-    match def_type {
-        "Void" => true,
-        "Bool" => true,
-        "Int" => true,
-        "Hyper" => true,
-        "UInt" => true,
-        "UHyper" => true,
-        "Float" => true,
-        "Double" => true,
-        "quadruple" => true,
-        _ => false,
-    }
+    matches!(
+        def_type,
+        "Void" | "Bool" | "Int" | "Hyper" | "UInt" | "UHyper" | "Float" | "Double" | "quadruple"
+    )
 }
 
 fn is_built_in_single(def_type: &str) -> bool {
@@ -149,12 +137,14 @@ impl CodeGenerator for CommonJsGenerator {
             (_, typ, size, _) if !is_array_type(typ) && size > 0 => format!("new _xdrJsSerialize.default.FixedArray({}, {})", size, typ),
             _ => format!("new _xdrJsSerialize.default.{}('', {})", typ, size)
         });
-        handlebars_helper!(isvoid: |x: str| x == "");
+        handlebars_helper!(isvoid: |x: str| x.is_empty());
+        handlebars_helper!(jsdoc: |comment: str, members: Json, key: str| js_doc(comment, members.as_array().map_or(&[], |m| m.as_slice()), key));
+        reg.register_helper("js-doc", Box::new(jsdoc));
         reg.register_helper("isvoid", Box::new(isvoid));
         reg.register_helper("typeconv", Box::new(typeconv));
         reg.register_escape_fn(|s| s.into());
         let result = reg.render_template(file_t.into_boxed_str().as_ref(), &processed).unwrap();
 
-        return Ok(result);
+        Ok(result)
     }
 }
@@ -0,0 +1,531 @@
+use super::*;
+use handlebars::Handlebars;
+use std::collections::HashMap;
+
+static MARKDOWN_T: &str = r#"{{#each namespaces as |ns|}}
+# Namespace `{{ns.name}}`
+
+{{#if ns.typedefs}}
+- Typedefs: {{#each ns.typedefs as |td|}}{{#if @index}}, {{/if}}[{{td.name}}](#{{td.anchor}}){{/each}}
+{{/if}}
+{{#if ns.structs}}
+- Structs: {{#each ns.structs as |st|}}{{#if @index}}, {{/if}}[{{st.name}}](#{{st.anchor}}){{/each}}
+{{/if}}
+{{#if ns.enums}}
+- Enums: {{#each ns.enums as |en|}}{{#if @index}}, {{/if}}[{{en.name}}](#{{en.anchor}}){{/each}}
+{{/if}}
+{{#if ns.unions}}
+- Unions: {{#each ns.unions as |un|}}{{#if @index}}, {{/if}}[{{un.name}}](#{{un.anchor}}){{/each}}
+{{/if}}
+{{#if ns.typedefs}}
+
+## Typedefs
+{{#each ns.typedefs as |td|}}
+
+<a id="{{td.anchor}}"></a>
+### {{td.name}}
+{{#if td.comment}}
+
+{{td.comment}}
+{{/if}}
+
+Type: {{> md-type td.decl}}{{#if td.decl.size}} ({{td.decl.size}}){{/if}}
+{{/each}}
+{{/if}}
+{{#if ns.structs}}
+
+## Structs
+{{#each ns.structs as |st|}}
+
+<a id="{{st.anchor}}"></a>
+### {{st.name}}
+{{#if st.comment}}
+
+{{st.comment}}
+{{/if}}
+
+| Field | Type | Size | Description |
+| --- | --- | --- | --- |
+{{#each st.fields as |field|}}
+| `{{field.name}}` | {{> md-type field}} | {{field.size}} | {{field.inline}} |
+{{/each}}
+{{/each}}
+{{/if}}
+{{#if ns.enums}}
+
+## Enums
+{{#each ns.enums as |en|}}
+
+<a id="{{en.anchor}}"></a>
+### {{en.name}}
+{{#if en.comment}}
+
+{{en.comment}}
+{{/if}}
+
+| Name | Value | Description |
+| --- | --- | --- |
+{{#each en.values as |val|}}
+| `{{val.name}}` | {{val.value}} | {{val.inline}} |
+{{/each}}
+{{/each}}
+{{/if}}
+{{#if ns.unions}}
+
+## Unions
+{{#each ns.unions as |un|}}
+
+<a id="{{un.anchor}}"></a>
+### {{un.name}}
+{{#if un.comment}}
+
+{{un.comment}}
+{{/if}}
+
+Switch: `{{un.discriminant.name}}` of type {{> md-type un.discriminant}}
+
+| Case | Value | Arm | Type | Size | Description |
+| --- | --- | --- | --- | --- | --- |
+{{#each un.arms as |arm|}}
+| `{{arm.case}}` | {{arm.value}} | {{#if arm.field}}`{{arm.field.name}}` | {{> md-type arm.field}} | {{arm.field.size}}{{else}}void | | {{/if}} | {{arm.inline}} |
+{{/each}}
+{{/each}}
+{{/if}}
+{{/each}}
+"#;
+
+static MD_TYPE_T: &str = r#"{{#if anchor}}[{{type_name}}](#{{anchor}}){{else}}`{{type_name}}`{{/if}}"#;
+
+static HTML_T: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{{title}}</title>
+<style>
+body { font-family: sans-serif; max-width: 60em; margin: 2em auto; color: #222; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+code { background: #f4f4f4; padding: 0 0.2em; }
+.comment { white-space: pre-line; }
+</style>
+</head>
+<body>
+{{#each namespaces as |ns|}}
+<h1>Namespace <code>{{ns.name}}</code></h1>
+<ul>
+{{#if ns.typedefs}}
+<li>Typedefs: {{#each ns.typedefs as |td|}}{{#if @index}}, {{/if}}<a href="#{{td.anchor}}">{{td.name}}</a>{{/each}}</li>
+{{/if}}
+{{#if ns.structs}}
+<li>Structs: {{#each ns.structs as |st|}}{{#if @index}}, {{/if}}<a href="#{{st.anchor}}">{{st.name}}</a>{{/each}}</li>
+{{/if}}
+{{#if ns.enums}}
+<li>Enums: {{#each ns.enums as |en|}}{{#if @index}}, {{/if}}<a href="#{{en.anchor}}">{{en.name}}</a>{{/each}}</li>
+{{/if}}
+{{#if ns.unions}}
+<li>Unions: {{#each ns.unions as |un|}}{{#if @index}}, {{/if}}<a href="#{{un.anchor}}">{{un.name}}</a>{{/each}}</li>
+{{/if}}
+</ul>
+{{#if ns.typedefs}}
+<h2>Typedefs</h2>
+{{#each ns.typedefs as |td|}}
+<h3 id="{{td.anchor}}">{{td.name}}</h3>
+{{#if td.comment}}
+<p class="comment">{{td.comment}}</p>
+{{/if}}
+<p>Type: {{> html-type td.decl}}{{#if td.decl.size}} ({{td.decl.size}}){{/if}}</p>
+{{/each}}
+{{/if}}
+{{#if ns.structs}}
+<h2>Structs</h2>
+{{#each ns.structs as |st|}}
+<h3 id="{{st.anchor}}">{{st.name}}</h3>
+{{#if st.comment}}
+<p class="comment">{{st.comment}}</p>
+{{/if}}
+<table>
+<tr><th>Field</th><th>Type</th><th>Size</th><th>Description</th></tr>
+{{#each st.fields as |field|}}
+<tr><td><code>{{field.name}}</code></td><td>{{> html-type field}}</td><td>{{field.size}}</td><td class="comment">{{field.comment}}</td></tr>
+{{/each}}
+</table>
+{{/each}}
+{{/if}}
+{{#if ns.enums}}
+<h2>Enums</h2>
+{{#each ns.enums as |en|}}
+<h3 id="{{en.anchor}}">{{en.name}}</h3>
+{{#if en.comment}}
+<p class="comment">{{en.comment}}</p>
+{{/if}}
+<table>
+<tr><th>Name</th><th>Value</th><th>Description</th></tr>
+{{#each en.values as |val|}}
+<tr><td><code>{{val.name}}</code></td><td>{{val.value}}</td><td class="comment">{{val.comment}}</td></tr>
+{{/each}}
+</table>
+{{/each}}
+{{/if}}
+{{#if ns.unions}}
+<h2>Unions</h2>
+{{#each ns.unions as |un|}}
+<h3 id="{{un.anchor}}">{{un.name}}</h3>
+{{#if un.comment}}
+<p class="comment">{{un.comment}}</p>
+{{/if}}
+<p>Switch: <code>{{un.discriminant.name}}</code> of type {{> html-type un.discriminant}}</p>
+<table>
+<tr><th>Case</th><th>Value</th><th>Arm</th><th>Type</th><th>Size</th><th>Description</th></tr>
+{{#each un.arms as |arm|}}
+<tr><td><code>{{arm.case}}</code></td><td>{{arm.value}}</td>{{#if arm.field}}<td><code>{{arm.field.name}}</code></td><td>{{> html-type arm.field}}</td><td>{{arm.field.size}}</td>{{else}}<td>void</td><td></td><td></td>{{/if}}<td class="comment">{{arm.comment}}</td></tr>
+{{/each}}
+</table>
+{{/each}}
+{{/if}}
+{{/each}}
+</body>
+</html>
+"##;
+
+static HTML_TYPE_T: &str = r##"{{#if anchor}}<a href="#{{anchor}}">{{type_name}}</a>{{else}}<code>{{type_name}}</code>{{/if}}"##;
+
+/// Length of `<>` declarations, which have no maximum.
+const UNBOUNDED: i32 = 2147483647;
+
+/// Renders browsable documentation of a schema as Markdown or, with `html`,
+/// a standalone HTML page. Declared types link to their definitions and
+/// doc comments from the schema are kept.
+#[derive(Debug, Default)]
+pub struct DocsGenerator {
+    pub html: bool,
+}
+
+#[derive(Serialize)]
+struct DocsOut {
+    title: String,
+    namespaces: Vec<NamespaceOut>,
+}
+
+#[derive(Serialize)]
+struct NamespaceOut {
+    name: String,
+    typedefs: Vec<TypedefOut>,
+    structs: Vec<StructOut>,
+    enums: Vec<EnumOut>,
+    unions: Vec<UnionOut>,
+}
+
+#[derive(Serialize)]
+struct FieldOut {
+    name: String,
+    type_name: String,
+    /// Anchor of the definition of a declared type.
+    anchor: Option<String>,
+    /// Human readable size limit, empty for single values.
+    size: String,
+    comment: String,
+    /// The comment on one line for table cells.
+    inline: String,
+}
+
+#[derive(Serialize)]
+struct TypedefOut {
+    name: String,
+    anchor: String,
+    comment: String,
+    decl: FieldOut,
+}
+
+#[derive(Serialize)]
+struct StructOut {
+    name: String,
+    anchor: String,
+    comment: String,
+    fields: Vec<FieldOut>,
+}
+
+#[derive(Serialize)]
+struct EnumValueOut {
+    name: String,
+    value: i32,
+    comment: String,
+    inline: String,
+}
+
+#[derive(Serialize)]
+struct EnumOut {
+    name: String,
+    anchor: String,
+    comment: String,
+    values: Vec<EnumValueOut>,
+}
+
+#[derive(Serialize)]
+struct ArmOut {
+    case: String,
+    value: i32,
+    /// Arm declaration, absent for void arms.
+    field: Option<FieldOut>,
+    comment: String,
+    inline: String,
+}
+
+#[derive(Serialize)]
+struct UnionOut {
+    name: String,
+    anchor: String,
+    comment: String,
+    discriminant: FieldOut,
+    arms: Vec<ArmOut>,
+}
+
+/// Puts a comment on one line and escapes `|` so it fits a Markdown table
+/// cell.
+fn inline(comment: &str) -> String {
+    comment.split_whitespace().collect::<Vec<_>>().join(" ").replace('|', "\\|")
+}
+
+fn anchor(namespace: &str, typ: &str) -> String {
+    format!("{}.{}", namespace, typ)
+}
+
+fn size(def: &Def) -> String {
+    let size = def.array_size;
+    let unit = match def.type_name.as_str() {
+        "string" | "opaque" => "bytes",
+        _ => "items",
+    };
+    match (size, def.fixed_array) {
+        (0, _) => String::new(),
+        (UNBOUNDED, _) => String::from("unbounded"),
+        (size, true) => format!("exactly {} {}", size, unit),
+        (size, false) => format!("up to {} {}", size, unit),
+    }
+}
+
+struct Types<'a> {
+    owners: HashMap<&'a str, &'a str>,
+    enums: HashMap<&'a str, &'a Enum>,
+}
+
+impl<'a> Types<'a> {
+    fn new(namespaces: &'a [Namespace]) -> Self {
+        let mut owners = HashMap::new();
+        let mut enums = HashMap::new();
+        for ns in namespaces {
+            let defs = ns.typedefs.iter().map(|td| td.def.name.as_str());
+            let structs = ns.structs.iter().map(|st| st.name.as_str());
+            let enum_names = ns.enums.iter().map(|en| en.name.as_str());
+            let unions = ns.unions.iter().map(|un| un.name.as_str());
+            for name in defs.chain(structs).chain(enum_names).chain(unions) {
+                owners.insert(name, ns.name.as_str());
+            }
+            for en in &ns.enums {
+                enums.insert(en.name.as_str(), en);
+            }
+        }
+        Types { owners, enums }
+    }
+
+    fn field(&self, def: &Def) -> FieldOut {
+        FieldOut {
+            name: def.name.clone(),
+            type_name: def.type_name.clone(),
+            anchor: self.owners.get(def.type_name.as_str()).map(|ns| anchor(ns, &def.type_name)),
+            size: size(def),
+            comment: def.comment.clone(),
+            inline: inline(&def.comment),
+        }
+    }
+
+    fn union(&self, ns: &str, un: &Union) -> Result<UnionOut, &'static str> {
+        let switch = &un.switch;
+        let en = self.enums.get(switch.enum_type.as_str());
+        let arms = switch
+            .cases
+            .iter()
+            .map(|case| {
+                let value = match en {
+                    Some(en) => en
+                        .values
+                        .iter()
+                        .find(|v| v.name == case.value)
+                        .map(|v| v.index)
+                        .ok_or("union case is not a value of its enum")?,
+                    None => case.value.parse().map_err(|_| "union case is not an integer")?,
+                };
+                let field = match case.ret_type.name.is_empty() {
+                    true => None,
+                    false => Some(self.field(&case.ret_type)),
+                };
+                let comment = [case.comment.as_str(), case.ret_type.comment.as_str()]
+                    .iter()
+                    .filter(|c| !c.is_empty())
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Ok(ArmOut {
+                    case: case.value.clone(),
+                    value,
+                    field,
+                    inline: inline(&comment),
+                    comment,
+                })
+            })
+            .collect::<Result<_, &'static str>>()?;
+        Ok(UnionOut {
+            name: un.name.clone(),
+            anchor: anchor(ns, &un.name),
+            comment: un.comment.clone(),
+            discriminant: self.field(&Def {
+                name: switch.enum_name.clone(),
+                type_name: switch.enum_type.clone(),
+                ..Default::default()
+            }),
+            arms,
+        })
+    }
+
+    fn namespace(&self, ns: &Namespace) -> Result<NamespaceOut, &'static str> {
+        Ok(NamespaceOut {
+            name: ns.name.clone(),
+            typedefs: ns
+                .typedefs
+                .iter()
+                .map(|td| TypedefOut {
+                    name: td.def.name.clone(),
+                    anchor: anchor(&ns.name, &td.def.name),
+                    comment: td.def.comment.clone(),
+                    decl: self.field(&td.def),
+                })
+                .collect(),
+            structs: ns
+                .structs
+                .iter()
+                .map(|st| StructOut {
+                    name: st.name.clone(),
+                    anchor: anchor(&ns.name, &st.name),
+                    comment: st.comment.clone(),
+                    fields: st.props.iter().map(|prop| self.field(prop)).collect(),
+                })
+                .collect(),
+            enums: ns
+                .enums
+                .iter()
+                .map(|en| EnumOut {
+                    name: en.name.clone(),
+                    anchor: anchor(&ns.name, &en.name),
+                    comment: en.comment.clone(),
+                    values: en
+                        .values
+                        .iter()
+                        .map(|v| EnumValueOut {
+                            name: v.name.clone(),
+                            value: v.index,
+                            comment: v.comment.clone(),
+                            inline: inline(&v.comment),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            unions: ns
+                .unions
+                .iter()
+                .map(|un| self.union(&ns.name, un))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl CodeGenerator for DocsGenerator {
+    fn code(&self, namespaces: Vec<Namespace>) -> Result<String, &'static str> {
+        let types = Types::new(&namespaces);
+        let docs = DocsOut {
+            title: namespaces.iter().map(|ns| ns.name.as_str()).collect::<Vec<_>>().join(", "),
+            namespaces: namespaces.iter().map(|ns| types.namespace(ns)).collect::<Result<_, _>>()?,
+        };
+        let mut reg = Handlebars::new();
+        let template = match self.html {
+            true => {
+                reg.register_partial("html-type", HTML_TYPE_T).unwrap();
+                HTML_T
+            }
+            false => {
+                reg.register_escape_fn(handlebars::no_escape);
+                reg.register_partial("md-type", MD_TYPE_T).unwrap();
+                MARKDOWN_T
+            }
+        };
+        reg.render_template(template, &docs)
+            .map_err(|_| "failed to render the documentation")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn namespaces() -> Vec<Namespace> {
+        build_namespaces(String::from(
+            "namespace test
+{
+    // Hash of a <block>.
+    typedef opaque Hash[32];
+
+    enum Kind
+    {
+        A = 0,
+        B = 5 // Second | last kind.
+    };
+
+    // An item.
+    struct Item
+    {
+        // Item hashes.
+        Hash hashes<4>;
+        string name<>;
+    };
+
+    union Body switch (Kind Type)
+    {
+        // Carries an item.
+        case A:
+            Item item;
+        case B:
+            void;
+    }
+}",
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn markdown() {
+        let res = DocsGenerator::default().code(namespaces());
+        assert!(res.is_ok());
+        let docs = res.unwrap();
+        assert!(docs.contains("# Namespace `test`\n"));
+        assert!(docs.contains("- Structs: [Item](#test.Item)\n"));
+        assert!(docs.contains("<a id=\"test.Hash\"></a>\n### Hash\n\nHash of a <block>.\n\nType: `opaque` (exactly 32 bytes)\n"));
+        assert!(docs.contains("| `hashes` | [Hash](#test.Hash) | up to 4 items | Item hashes. |\n"));
+        assert!(docs.contains("| `name` | `string` | unbounded |  |\n"));
+        assert!(docs.contains("| `B` | 5 | Second \\| last kind. |\n"));
+        assert!(docs.contains("Switch: `Type` of type [Kind](#test.Kind)\n"));
+        assert!(docs.contains("| `A` | 0 | `item` | [Item](#test.Item) |  | Carries an item. |\n"));
+        assert!(docs.contains("| `B` | 5 | void | |  |  |\n"));
+    }
+
+    #[test]
+    fn html() {
+        let res = DocsGenerator { html: true }.code(namespaces());
+        assert!(res.is_ok());
+        let docs = res.unwrap();
+        assert!(docs.starts_with("<!DOCTYPE html>"));
+        assert!(docs.contains("<h3 id=\"test.Hash\">Hash</h3>\n<p class=\"comment\">Hash of a &lt;block&gt;.</p>"));
+        assert!(docs
+            .contains("<td><a href=\"#test.Hash\">Hash</a></td><td>up to 4 items</td><td class=\"comment\">Item hashes.</td>"));
+        assert!(
+            docs.contains("<tr><td><code>B</code></td><td>5</td><td>void</td><td></td><td></td><td class=\"comment\"></td></tr>")
+        );
+    }
+}
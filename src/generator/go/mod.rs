@@ -3,20 +3,6 @@ use handlebars::Handlebars;
 use std::collections::HashMap;
 
 static HEADER: &str = r#"
-// Package xdr is automatically generated
-// DO NOT EDIT or your changes may be overwritten
-package xdr
-
-import (
-  "bytes"
-  "encoding"
-  "encoding/json"
-  "io"
-  "fmt"
-
-  "github.com/stellar/go-xdr/xdr3"
-)
-
 // Unmarshal reads an xdr element from `r` into `v`.
 func Unmarshal(r io.Reader, v interface{}) (int, error) {
   // delegate to xdr package's Unmarshal
@@ -28,7 +14,405 @@ func Marshal(w io.Writer, v interface{}) (int, error) {
   // delegate to xdr package's Marshal
   return xdr.Marshal(w, v)
 }
-{{#each this as |ns| ~}}
+"#;
+
+static SHARED_RUNTIME_HEADER: &str = r#"
+// Encoder is the shared XDR encoder.
+type Encoder = {{runtime}}.Encoder
+
+// Decoder is the shared XDR decoder.
+type Decoder = {{runtime}}.Decoder
+
+// Encodable is implemented by every generated type.
+type Encodable = {{runtime}}.Encodable
+
+// Decodable is implemented by pointers to every generated type.
+type Decodable = {{runtime}}.Decodable
+
+// Unmarshal reads an xdr element from `r` into `v`.
+func Unmarshal(r io.Reader, v interface{}) (int, error) {
+  return {{runtime}}.Unmarshal(r, v)
+}
+
+// Marshal writes an xdr element `v` into `w`.
+func Marshal(w io.Writer, v interface{}) (int, error) {
+  return {{runtime}}.Marshal(w, v)
+}
+"#;
+
+static RUNTIME_T: &str = r#"
+// Encodable is implemented by every generated type.
+type Encodable interface {
+  EncodeTo(e *Encoder) error
+}
+
+// Decodable is implemented by pointers to every generated type.
+type Decodable interface {
+  DecodeFrom(d *Decoder) error
+}
+
+// Unmarshal reads an xdr element from `r` into `v`.
+func Unmarshal(r io.Reader, v interface{}) (int, error) {
+  dv, ok := v.(Decodable)
+  if !ok {
+    return 0, fmt.Errorf("xdr: %T does not implement Decodable", v)
+  }
+  d := NewDecoder(r)
+  err := dv.DecodeFrom(d)
+  return d.Read(), err
+}
+
+// Marshal writes an xdr element `v` into `w`.
+func Marshal(w io.Writer, v interface{}) (int, error) {
+  ev, ok := v.(Encodable)
+  if !ok {
+    return 0, fmt.Errorf("xdr: %T does not implement Encodable", v)
+  }
+  e := NewEncoder(w)
+  err := ev.EncodeTo(e)
+  return e.Written(), err
+}
+
+// Encoder writes XDR primitives to an io.Writer.
+type Encoder struct {
+  w       io.Writer
+  scratch [8]byte
+  written int
+}
+
+// NewEncoder returns an Encoder writing to `w`.
+func NewEncoder(w io.Writer) *Encoder {
+  return &Encoder{w: w}
+}
+
+// Written returns the number of bytes written so far.
+func (e *Encoder) Written() int {
+  return e.written
+}
+
+func (e *Encoder) write(b []byte) error {
+  n, err := e.w.Write(b)
+  e.written += n
+  return err
+}
+
+func (e *Encoder) pad(n int) error {
+  if p := (4 - n%4) % 4; p > 0 {
+    e.scratch = [8]byte{}
+    return e.write(e.scratch[:p])
+  }
+  return nil
+}
+
+// EncodeUint writes an unsigned int.
+func (e *Encoder) EncodeUint(v uint32) error {
+  binary.BigEndian.PutUint32(e.scratch[:4], v)
+  return e.write(e.scratch[:4])
+}
+
+// EncodeInt writes an int.
+func (e *Encoder) EncodeInt(v int32) error {
+  return e.EncodeUint(uint32(v))
+}
+
+// EncodeUhyper writes an unsigned hyper.
+func (e *Encoder) EncodeUhyper(v uint64) error {
+  binary.BigEndian.PutUint64(e.scratch[:8], v)
+  return e.write(e.scratch[:8])
+}
+
+// EncodeHyper writes a hyper.
+func (e *Encoder) EncodeHyper(v int64) error {
+  return e.EncodeUhyper(uint64(v))
+}
+
+// EncodeFloat writes a float.
+func (e *Encoder) EncodeFloat(v float32) error {
+  return e.EncodeUint(math.Float32bits(v))
+}
+
+// EncodeDouble writes a double.
+func (e *Encoder) EncodeDouble(v float64) error {
+  return e.EncodeUhyper(math.Float64bits(v))
+}
+
+// EncodeBool writes a boolean.
+func (e *Encoder) EncodeBool(v bool) error {
+  if v {
+    return e.EncodeUint(1)
+  }
+  return e.EncodeUint(0)
+}
+
+// EncodeLen writes the length prefix of a variable length array.
+func (e *Encoder) EncodeLen(n int, max int) error {
+  if n > max {
+    return fmt.Errorf("xdr: length %d exceeds maximum %d", n, max)
+  }
+  return e.EncodeUint(uint32(n))
+}
+
+// EncodeFixedOpaque writes fixed length opaque data.
+func (e *Encoder) EncodeFixedOpaque(v []byte, size int) error {
+  if len(v) != size {
+    return fmt.Errorf("xdr: opaque length %d does not match %d", len(v), size)
+  }
+  if err := e.write(v); err != nil {
+    return err
+  }
+  return e.pad(len(v))
+}
+
+// EncodeOpaque writes variable length opaque data.
+func (e *Encoder) EncodeOpaque(v []byte, max int) error {
+  if err := e.EncodeLen(len(v), max); err != nil {
+    return err
+  }
+  if err := e.write(v); err != nil {
+    return err
+  }
+  return e.pad(len(v))
+}
+
+// EncodeString writes a string.
+func (e *Encoder) EncodeString(v string, max int) error {
+  if err := e.EncodeLen(len(v), max); err != nil {
+    return err
+  }
+  n, err := io.WriteString(e.w, v)
+  e.written += n
+  if err != nil {
+    return err
+  }
+  return e.pad(len(v))
+}
+
+// Decoder reads XDR primitives from an io.Reader.
+type Decoder struct {
+  r       io.Reader
+  scratch [8]byte
+  read    int
+}
+
+// NewDecoder returns a Decoder reading from `r`.
+func NewDecoder(r io.Reader) *Decoder {
+  return &Decoder{r: r}
+}
+
+// Read returns the number of bytes read so far.
+func (d *Decoder) Read() int {
+  return d.read
+}
+
+func (d *Decoder) fill(b []byte) error {
+  n, err := io.ReadFull(d.r, b)
+  d.read += n
+  return err
+}
+
+func (d *Decoder) pad(n int) error {
+  if p := (4 - n%4) % 4; p > 0 {
+    if err := d.fill(d.scratch[:p]); err != nil {
+      return err
+    }
+    for _, b := range d.scratch[:p] {
+      if b != 0 {
+        return fmt.Errorf("xdr: non-zero padding")
+      }
+    }
+  }
+  return nil
+}
+
+// DecodeUint reads an unsigned int.
+func (d *Decoder) DecodeUint(v *uint32) error {
+  if err := d.fill(d.scratch[:4]); err != nil {
+    return err
+  }
+  *v = binary.BigEndian.Uint32(d.scratch[:4])
+  return nil
+}
+
+// DecodeInt reads an int.
+func (d *Decoder) DecodeInt(v *int32) error {
+  var u uint32
+  if err := d.DecodeUint(&u); err != nil {
+    return err
+  }
+  *v = int32(u)
+  return nil
+}
+
+// DecodeUhyper reads an unsigned hyper.
+func (d *Decoder) DecodeUhyper(v *uint64) error {
+  if err := d.fill(d.scratch[:8]); err != nil {
+    return err
+  }
+  *v = binary.BigEndian.Uint64(d.scratch[:8])
+  return nil
+}
+
+// DecodeHyper reads a hyper.
+func (d *Decoder) DecodeHyper(v *int64) error {
+  var u uint64
+  if err := d.DecodeUhyper(&u); err != nil {
+    return err
+  }
+  *v = int64(u)
+  return nil
+}
+
+// DecodeFloat reads a float.
+func (d *Decoder) DecodeFloat(v *float32) error {
+  var u uint32
+  if err := d.DecodeUint(&u); err != nil {
+    return err
+  }
+  *v = math.Float32frombits(u)
+  return nil
+}
+
+// DecodeDouble reads a double.
+func (d *Decoder) DecodeDouble(v *float64) error {
+  var u uint64
+  if err := d.DecodeUhyper(&u); err != nil {
+    return err
+  }
+  *v = math.Float64frombits(u)
+  return nil
+}
+
+// DecodeBool reads a boolean.
+func (d *Decoder) DecodeBool(v *bool) error {
+  var u uint32
+  if err := d.DecodeUint(&u); err != nil {
+    return err
+  }
+  switch u {
+  case 0:
+    *v = false
+  case 1:
+    *v = true
+  default:
+    return fmt.Errorf("xdr: invalid boolean %d", u)
+  }
+  return nil
+}
+
+// DecodeLen reads the length prefix of a variable length array.
+func (d *Decoder) DecodeLen(max int) (int, error) {
+  var u uint32
+  if err := d.DecodeUint(&u); err != nil {
+    return 0, err
+  }
+  if int64(u) > int64(max) {
+    return 0, fmt.Errorf("xdr: length %d exceeds maximum %d", u, max)
+  }
+  return int(u), nil
+}
+
+// DecodeFixedOpaque reads len(v) bytes of opaque data into `v`.
+func (d *Decoder) DecodeFixedOpaque(v []byte) error {
+  if err := d.fill(v); err != nil {
+    return err
+  }
+  return d.pad(len(v))
+}
+
+// DecodeOpaque reads variable length opaque data, reusing the capacity of `v`.
+func (d *Decoder) DecodeOpaque(v *[]byte, max int) error {
+  n, err := d.DecodeLen(max)
+  if err != nil {
+    return err
+  }
+  if cap(*v) >= n {
+    *v = (*v)[:n]
+    return d.DecodeFixedOpaque(*v)
+  }
+  b, err := d.readBytes(n)
+  if err != nil {
+    return err
+  }
+  *v = b
+  return nil
+}
+
+// DecodeString reads a string.
+func (d *Decoder) DecodeString(v *string, max int) error {
+  n, err := d.DecodeLen(max)
+  if err != nil {
+    return err
+  }
+  b, err := d.readBytes(n)
+  if err != nil {
+    return err
+  }
+  *v = string(b)
+  return nil
+}
+
+// chunkSize bounds each allocation made for data whose length comes from the
+// input, so a forged length prefix cannot force a large allocation.
+const chunkSize = 1 << 16
+
+// readBytes reads `n` bytes and their padding, growing the buffer as the data
+// arrives.
+func (d *Decoder) readBytes(n int) ([]byte, error) {
+  var b []byte
+  for len(b) < n {
+    m := n - len(b)
+    if m > chunkSize {
+      m = chunkSize
+    }
+    b = append(b, make([]byte, m)...)
+    if err := d.fill(b[len(b)-m:]); err != nil {
+      return nil, err
+    }
+  }
+  return b, d.pad(n)
+}
+
+// SliceCap returns the initial capacity of a slice of `n` elements read from
+// the input. Slices grow as their elements decode.
+func (d *Decoder) SliceCap(n int) int {
+  if n > 64 {
+    return 64
+  }
+  return n
+}
+
+"#;
+
+static VALIDATE_HELPERS_T: &str = r#"
+// ValidationError reports a value violating its XDR bounds, with the path of
+// the offending field.
+type ValidationError struct {
+  Path string
+  Msg  string
+}
+
+// Error implements the error interface.
+func (e *ValidationError) Error() string {
+  if e.Path == "" {
+    return e.Msg
+  }
+  return e.Path + ": " + e.Msg
+}
+
+func validationAt(err error, name string) error {
+  verr, ok := err.(*ValidationError)
+  if !ok || name == "" {
+    return err
+  }
+  path := name
+  if verr.Path != "" && verr.Path[0] != '[' {
+    path += "."
+  }
+  return &ValidationError{Path: path + verr.Path, Msg: verr.Msg}
+}
+"#;
+
+static NAMESPACE_START: &str = r#"{{#each namespaces as |ns| ~}}
 // Namspace start {{ns.name}}
 "#;
 
@@ -39,6 +423,10 @@ static TYPEDEFS_T: &str = r#"
 {{#if td.def.array_size}}
 {{#if td.def.fixed_array}}
 // {{td.def.name}} generated typedef
+{{#if td.def.comment}}
+//
+{{doc td.def.comment "// "}}
+{{/if}}
 type {{td.def.name}} {{#if (neqstr td.def.type_name) }}[{{td.def.array_size}}]{{/if}}{{td.def.type_name}}
 // XDRMaxSize implements the Sized interface for {{td.def.name}}
 func (s {{td.def.name}}) XDRMaxSize() int {
@@ -46,6 +434,10 @@ func (s {{td.def.name}}) XDRMaxSize() int {
 }
 {{else}}
 // {{td.def.name}} generated typedef
+{{#if td.def.comment}}
+//
+{{doc td.def.comment "// "}}
+{{/if}}
 type {{td.def.name}} {{#if (neqstr td.def.type_name) }}[]{{/if}}{{td.def.type_name}}
 {{/if}}
 {{/if}}
@@ -77,8 +469,15 @@ static STRUCTS_T: &str = r#"
 {{#each ns.structs as |st| ~}}
 
 // {{st.name}} generated struct
+{{#if st.comment}}
+//
+{{doc st.comment "// "}}
+{{/if}}
 type {{st.name}} struct {
 {{#each st.props as |prop|}}
+{{#if prop.comment}}
+{{doc prop.comment "  // "}}
+{{/if}}
 {{#if (eqstr prop.type_name)}}
   {{#if (and (ne prop.array_size 0) (ne prop.array_size 2147483647))}}
     {{prop.name}} string `xdrmaxsize:"{{prop.array_size}}" json:"{{lower prop.name}}"`
@@ -131,10 +530,18 @@ static ENUM_T: &str = r#"
 
 {{#each ns.enums as |enum|}}
 // {{enum.name}} generated enum
+{{#if enum.comment}}
+//
+{{doc enum.comment "// "}}
+{{/if}}
 type {{enum.name}} int32
 const (
 {{#each enum.values as |val|}}
   // {{enum.name}}{{val.name}} enum value {{val.index}}
+{{#if val.comment}}
+  //
+{{doc val.comment "  // "}}
+{{/if}}
   {{enum.name}}{{val.name}} {{enum.name}} = {{val.index}}
 {{/each~}}
 )
@@ -174,6 +581,69 @@ var (
   _ encoding.BinaryMarshaler   = (*{{enum.name}})(nil)
   _ encoding.BinaryUnmarshaler = (*{{enum.name}})(nil)
 )
+{{#if @root.options.enum_names}}
+
+// {{enum.name}}Names maps values to their schema names, used for JSON and text.
+var {{enum.name}}Names = map[int32]string{
+{{#each enum.values as |val|}}
+  {{val.index}}: "{{val.name}}",
+{{/each~}}
+}
+
+// MarshalText implements encoding.TextMarshaler using {{enum.name}}Names.
+func (s {{enum.name}}) MarshalText() ([]byte, error) {
+  name, ok := {{enum.name}}Names[int32(s)]
+  if !ok {
+    return nil, fmt.Errorf("invalid {{enum.name}} value %d", int32(s))
+  }
+  return []byte(name), nil
+}
+
+// UnmarshalText implements encoding.TextUnmarshaler, accepting a name from
+// {{enum.name}}Names or {{enum.name}}Map, or an integer value.
+func (s *{{enum.name}}) UnmarshalText(text []byte) error {
+  for value, name := range {{enum.name}}Names {
+    if name == string(text) || {{enum.name}}Map[value] == string(text) {
+      *s = {{enum.name}}(value)
+      return nil
+    }
+  }
+  var value int32
+  if err := json.Unmarshal(text, &value); err != nil {
+    return fmt.Errorf("invalid {{enum.name}} name %q", text)
+  }
+  if _, ok := {{enum.name}}Map[value]; !ok {
+    return fmt.Errorf("invalid {{enum.name}} value %d", value)
+  }
+  *s = {{enum.name}}(value)
+  return nil
+}
+
+// MarshalJSON implements json.Marshaler, encoding the enum by name.
+func (s {{enum.name}}) MarshalJSON() ([]byte, error) {
+  text, err := s.MarshalText()
+  if err != nil {
+    return nil, err
+  }
+  return json.Marshal(string(text))
+}
+
+// UnmarshalJSON implements json.Unmarshaler, accepting names or integers.
+func (s *{{enum.name}}) UnmarshalJSON(data []byte) error {
+  var name string
+  if err := json.Unmarshal(data, &name); err == nil {
+    return s.UnmarshalText([]byte(name))
+  }
+  return s.UnmarshalText(data)
+}
+
+var (
+  _ encoding.TextMarshaler   = (*{{enum.name}})(nil)
+  _ encoding.TextUnmarshaler = (*{{enum.name}})(nil)
+  _ json.Marshaler           = (*{{enum.name}})(nil)
+  _ json.Unmarshaler         = (*{{enum.name}})(nil)
+)
+{{/if}}
 {{/each~}}
 // End enum section
 "#;
@@ -183,10 +653,20 @@ static UNION_T: &str = r#"
 
 {{#each ns.unions as |uni|}}
 // {{uni.name}} generated union
+{{#if uni.comment}}
+//
+{{doc uni.comment "// "}}
+{{/if}}
 type {{uni.name}} struct{
   {{uni.switch.enum_name}} {{uni.switch.enum_type}}
 {{#each uni.switch.cases as |case|}}
 {{#if (not (isvoid case.ret_type.name))}}
+{{#if case.comment}}
+{{doc case.comment "  // "}}
+{{/if}}
+{{#if case.ret_type.comment}}
+{{doc case.ret_type.comment "  // "}}
+{{/if}}
     {{#if (eqstr case.ret_type.type_name)}}
         {{case.ret_type.name}} *{{case.ret_type.type_name}}
     {{else}} {{#if case.ret_type.array_size}}
@@ -293,6 +773,62 @@ var (
   _ encoding.BinaryUnmarshaler = (*{{uni.name}})(nil)
 )
 
+{{#if @root.options.union_names}}
+// MarshalJSON implements json.Marshaler, keying the arm by name.
+func (u {{uni.name}}) MarshalJSON() ([]byte, error) {
+  switch u.{{uni.switch.enum_name}} {
+{{#each uni.switch.cases as |case|}}
+  case {{uni.switch.enum_type}}{{case.value}}:
+{{#if (not (isvoid case.ret_type.name))}}
+    return json.Marshal(struct {
+      Type string `json:"type"`
+      {{case.ret_type.name}} *{{go-type case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array true}} `json:"{{lower case.ret_type.name}}"`
+    }{"{{case.value}}", u.{{case.ret_type.name}}})
+{{else}}
+    return json.Marshal(struct {
+      Type string `json:"type"`
+    }{"{{case.value}}"})
+{{/if}}
+{{/each}}
+  default:
+    return nil, fmt.Errorf("invalid union type")
+  }
+}
+
+// UnmarshalJSON implements json.Unmarshaler, reading the arm keyed by name.
+func (u *{{uni.name}}) UnmarshalJSON(data []byte) error {
+  temp := struct {
+    Type string `json:"type"`
+  }{}
+  if err := json.Unmarshal(data, &temp); err != nil {
+    return err
+  }
+
+  *u = {{uni.name}}{}
+  switch temp.Type {
+{{#each uni.switch.cases as |case|}}
+  case "{{case.value}}":
+    u.{{uni.switch.enum_name}} = {{uni.switch.enum_type}}{{case.value}}
+{{#if (not (isvoid case.ret_type.name))}}
+    response := struct {
+      {{case.ret_type.name}} *{{go-type case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array true}} `json:"{{lower case.ret_type.name}}"`
+    }{}
+    if err := json.Unmarshal(data, &response); err != nil {
+      return err
+    }
+    if response.{{case.ret_type.name}} == nil {
+      return fmt.Errorf("missing union arm {{lower case.ret_type.name}}")
+    }
+    u.{{case.ret_type.name}} = response.{{case.ret_type.name}}
+{{/if}}
+{{/each}}
+  default:
+    return fmt.Errorf("invalid union type %q", temp.Type)
+  }
+
+  return nil
+}
+{{else}}
 // MarshalJSON implements json.Marshaler.
 func (u {{uni.name}}) MarshalJSON() ([]byte, error) {
   temp := struct {
@@ -348,34 +884,891 @@ func (u *{{uni.name}}) UnmarshalJSON(data []byte) error {
 
   return nil
 }
+{{/if}}
 
 {{/each~}}
 // End union section
 "#;
 
-static FOOTER: &str = r#"
-// Namespace end {{ns.name}}
+static VALIDATE_T: &str = r#"
+// Start validation section
+{{#each ns.typedefs as |td|}}
+
+// Validate checks the XDR bounds of {{td.def.name}}.
+func (s {{td.def.name}}) Validate() error {
+  {{go-validate "s" "" td.def.type_name td.def.array_size td.def.fixed_array false}}
+  return nil
+}
+{{/each}}
+{{#each ns.structs as |st|}}
+
+// Validate checks the XDR bounds of {{st.name}}, recursively.
+func (s {{st.name}}) Validate() error {
+{{#each st.props as |prop|}}
+  {{go-validate (field "s" prop.name) prop.name prop.type_name prop.array_size prop.fixed_array false}}
+{{/each}}
+  return nil
+}
+{{/each}}
+{{#each ns.enums as |enum|}}
+
+// Validate checks that {{enum.name}} is a member of the enum.
+func (s {{enum.name}}) Validate() error {
+  if !s.ValidEnum(int32(s)) {
+    return &ValidationError{Msg: fmt.Sprintf("invalid {{enum.name}} value %d", int32(s))}
+  }
+  return nil
+}
+{{/each}}
+{{#each ns.unions as |uni|}}
+
+// Validate checks that the arm selected by {{uni.switch.enum_name}} is set and
+// within its XDR bounds, recursively.
+func (u {{uni.name}}) Validate() error {
+  if err := u.{{uni.switch.enum_name}}.Validate(); err != nil {
+    return validationAt(err, "{{uni.switch.enum_name}}")
+  }
+  switch u.{{uni.switch.enum_name}} {
+{{#each uni.switch.cases as |case|}}
+  case {{uni.switch.enum_type}}{{case.value}}:
+{{#if (not (isvoid case.ret_type.name))}}
+    if u.{{case.ret_type.name}} == nil {
+      return &ValidationError{Path: "{{case.ret_type.name}}", Msg: "arm is not set"}
+    }
+    {{go-validate (deref "u" case.ret_type.name) case.ret_type.name case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array true}}
+{{/if}}
+{{/each}}
+  }
+  return nil
+}
 {{/each~}}
-var fmtTest = fmt.Sprint("this is a dummy usage of fmt")
+// End validation section
 "#;
 
-#[derive(Debug, Default)]
-pub struct GoGenerator {}
+static COPY_T: &str = r#"
+// Start copy section
+{{#each ns.typedefs as |td|}}
 
-fn build_file_template() -> String {
-    format!("{}{}{}{}{}{}", HEADER, TYPEDEFS_T, STRUCTS_T, ENUM_T, UNION_T, FOOTER)
+// Clone returns a deep copy of {{td.def.name}}.
+func (s {{td.def.name}}) Clone() {{td.def.name}} {
+  c := s
+  {{go-clone "c" "s" td.def.type_name td.def.array_size td.def.fixed_array false}}
+  return c
 }
 
-fn process_namespaces(namespaces: Vec<Namespace>) -> Result<Vec<Namespace>, &'static str> {
-    let mut type_map = HashMap::new();
-    type_map.insert("boolean", "bool");
-    type_map.insert("opaque", "byte");
-    type_map.insert("int", "int32");
-    type_map.insert("unsigned int", "uint32");
-    type_map.insert("hyper", "int64");
-    type_map.insert("unsigned hyper", "uint64");
-    type_map.insert("float", "float32");
-    type_map.insert("double", "float64");
+// Equal reports whether {{td.def.name}} and `other` hold the same value.
+func (s {{td.def.name}}) Equal(other {{td.def.name}}) bool {
+  {{go-equal "s" "other" td.def.type_name td.def.array_size td.def.fixed_array false}}
+  return true
+}
+
+// Hash returns a stable FNV-1a hash of the XDR encoding of {{td.def.name}},
+// or the error encoding it.
+func (s {{td.def.name}}) Hash() (uint64, error) {
+  b, err := s.MarshalBinary()
+  if err != nil {
+    return 0, err
+  }
+  h := fnv.New64a()
+  _, _ = h.Write(b)
+  return h.Sum64(), nil
+}
+{{/each}}
+{{#each ns.structs as |st|}}
+
+// Clone returns a deep copy of {{st.name}}.
+func (s {{st.name}}) Clone() {{st.name}} {
+  c := s
+{{#each st.props as |prop|}}
+  {{go-clone (field "c" prop.name) (field "s" prop.name) prop.type_name prop.array_size prop.fixed_array false}}
+{{/each}}
+  return c
+}
+
+// Equal reports whether {{st.name}} and `other` hold the same value.
+func (s {{st.name}}) Equal(other {{st.name}}) bool {
+{{#each st.props as |prop|}}
+  {{go-equal (field "s" prop.name) (field "other" prop.name) prop.type_name prop.array_size prop.fixed_array false}}
+{{/each}}
+  return true
+}
+
+// Hash returns a stable FNV-1a hash of the XDR encoding of {{st.name}},
+// or the error encoding it.
+func (s {{st.name}}) Hash() (uint64, error) {
+  b, err := s.MarshalBinary()
+  if err != nil {
+    return 0, err
+  }
+  h := fnv.New64a()
+  _, _ = h.Write(b)
+  return h.Sum64(), nil
+}
+{{/each}}
+{{#each ns.enums as |enum|}}
+
+// Clone returns a copy of {{enum.name}}.
+func (s {{enum.name}}) Clone() {{enum.name}} {
+  return s
+}
+
+// Equal reports whether {{enum.name}} and `other` are the same value.
+func (s {{enum.name}}) Equal(other {{enum.name}}) bool {
+  return s == other
+}
+{{/each}}
+{{#each ns.unions as |uni|}}
+
+// Clone returns a deep copy of {{uni.name}}, including the value of the set arm.
+func (u {{uni.name}}) Clone() {{uni.name}} {
+  c := u
+{{#each uni.switch.cases as |case|}}
+{{#if (not (isvoid case.ret_type.name))}}
+  if u.{{case.ret_type.name}} != nil {
+    {{go-clone-arm "v" (deref "u" case.ret_type.name) case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array}}
+    c.{{case.ret_type.name}} = &v
+  }
+{{/if}}
+{{/each}}
+  return c
+}
+
+// Equal reports whether {{uni.name}} and `other` select the same arm with the
+// same value.
+func (u {{uni.name}}) Equal(other {{uni.name}}) bool {
+  if u.{{uni.switch.enum_name}} != other.{{uni.switch.enum_name}} {
+    return false
+  }
+{{#each uni.switch.cases as |case|}}
+{{#if (not (isvoid case.ret_type.name))}}
+  if (u.{{case.ret_type.name}} == nil) != (other.{{case.ret_type.name}} == nil) {
+    return false
+  }
+  if u.{{case.ret_type.name}} != nil {
+    {{go-equal (deref "u" case.ret_type.name) (deref "other" case.ret_type.name) case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array true}}
+  }
+{{/if}}
+{{/each}}
+  return true
+}
+
+// Hash returns a stable FNV-1a hash of the XDR encoding of {{uni.name}},
+// or the error encoding it.
+func (u {{uni.name}}) Hash() (uint64, error) {
+  b, err := u.MarshalBinary()
+  if err != nil {
+    return 0, err
+  }
+  h := fnv.New64a()
+  _, _ = h.Write(b)
+  return h.Sum64(), nil
+}
+{{/each~}}
+// End copy section
+"#;
+
+static CODEC_T: &str = r#"
+// Start codec section
+{{#each ns.typedefs as |td|}}
+
+// EncodeTo writes {{td.def.name}} to `e`.
+func (s {{td.def.name}}) EncodeTo(e *Encoder) error {
+  {{go-encode "s" td.def.type_name td.def.array_size td.def.fixed_array false}}
+  return nil
+}
+
+// DecodeFrom reads {{td.def.name}} from `d`.
+func (s *{{td.def.name}}) DecodeFrom(d *Decoder) error {
+  {{go-decode "(*s)" td.def.type_name td.def.array_size td.def.fixed_array false}}
+  return nil
+}
+{{/each}}
+{{#each ns.structs as |st|}}
+
+// EncodeTo writes {{st.name}} to `e`.
+func (s {{st.name}}) EncodeTo(e *Encoder) error {
+{{#each st.props as |prop|}}
+  {{go-encode (field "s" prop.name) prop.type_name prop.array_size prop.fixed_array false}}
+{{/each}}
+  return nil
+}
+
+// DecodeFrom reads {{st.name}} from `d`.
+func (s *{{st.name}}) DecodeFrom(d *Decoder) error {
+{{#each st.props as |prop|}}
+  {{go-decode (field "s" prop.name) prop.type_name prop.array_size prop.fixed_array false}}
+{{/each}}
+  return nil
+}
+{{/each}}
+{{#each ns.enums as |enum|}}
+
+// EncodeTo writes {{enum.name}} to `e`.
+func (s {{enum.name}}) EncodeTo(e *Encoder) error {
+  if _, ok := {{enum.name}}Map[int32(s)]; !ok {
+    return fmt.Errorf("xdr: invalid {{enum.name}} value %d", int32(s))
+  }
+  return e.EncodeInt(int32(s))
+}
+
+// DecodeFrom reads {{enum.name}} from `d`.
+func (s *{{enum.name}}) DecodeFrom(d *Decoder) error {
+  var v int32
+  if err := d.DecodeInt(&v); err != nil {
+    return err
+  }
+  if _, ok := {{enum.name}}Map[v]; !ok {
+    return fmt.Errorf("xdr: invalid {{enum.name}} value %d", v)
+  }
+  *s = {{enum.name}}(v)
+  return nil
+}
+{{/each}}
+{{#each ns.unions as |uni|}}
+
+// EncodeTo writes {{uni.name}} to `e`.
+func (u {{uni.name}}) EncodeTo(e *Encoder) error {
+  if err := u.{{uni.switch.enum_name}}.EncodeTo(e); err != nil {
+    return err
+  }
+  switch u.{{uni.switch.enum_name}} {
+{{#each uni.switch.cases as |case|}}
+  case {{uni.switch.enum_type}}{{case.value}}:
+{{#if (not (isvoid case.ret_type.name))}}
+    if u.{{case.ret_type.name}} == nil {
+      return fmt.Errorf("xdr: arm {{case.ret_type.name}} is not set")
+    }
+    {{go-encode (deref "u" case.ret_type.name) case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array true}}
+{{/if}}
+{{/each}}
+  default:
+    return fmt.Errorf("invalid union type")
+  }
+  return nil
+}
+
+// DecodeFrom reads {{uni.name}} from `d`.
+func (u *{{uni.name}}) DecodeFrom(d *Decoder) error {
+  *u = {{uni.name}}{}
+  if err := u.{{uni.switch.enum_name}}.DecodeFrom(d); err != nil {
+    return err
+  }
+  switch u.{{uni.switch.enum_name}} {
+{{#each uni.switch.cases as |case|}}
+  case {{uni.switch.enum_type}}{{case.value}}:
+{{#if (not (isvoid case.ret_type.name))}}
+    var val {{go-type case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array true}}
+    {{go-decode "val" case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array true}}
+    u.{{case.ret_type.name}} = &val
+{{/if}}
+{{/each}}
+  default:
+    return fmt.Errorf("invalid union type")
+  }
+  return nil
+}
+{{/each~}}
+// End codec section
+"#;
+
+static TEST_T: &str = r#"{{#*inline "round-trip"}}
+
+func Fuzz{{name}}(f *testing.F) {
+  f.Fuzz(func(t *testing.T, data []byte) {
+    var v {{name}}
+    fuzzRoundTrip(t, data, &v)
+  })
+}
+
+func TestRoundTrip{{name}}(t *testing.T) {
+  r := rand.New(rand.NewSource(1))
+  for i := 0; i < 100; i++ {
+    v := random{{name}}(r, 0)
+    b, err := v.MarshalBinary()
+    if err != nil {
+      t.Fatalf("encoding failed: %v", err)
+    }
+    var d {{name}}
+    if err := d.UnmarshalBinary(b); err != nil {
+      t.Fatalf("decoding failed: %v", err)
+    }
+{{#if @root.options.copy}}
+    if !v.Equal(d) {
+      t.Fatalf("round trip mismatch: %+v != %+v", v, d)
+    }
+{{else}}
+    e, err := d.MarshalBinary()
+    if err != nil {
+      t.Fatalf("re-encoding failed: %v", err)
+    }
+    if !bytes.Equal(b, e) {
+      t.Fatalf("round trip mismatch: %x != %x", b, e)
+    }
+{{/if}}
+  }
+}
+{{/inline}}
+
+// randomLen picks a length up to `max`, keeping generated values small and
+// cutting off recursion.
+func randomLen(r *rand.Rand, max int, depth int) int {
+  if depth > 3 {
+    return 0
+  }
+  if max > 8 {
+    max = 8
+  }
+  return r.Intn(max + 1)
+}
+
+func randomString(r *rand.Rand, max int) string {
+  b := make([]byte, randomLen(r, max, 0))
+  for i := range b {
+    b[i] = byte('a' + r.Intn(26))
+  }
+  return string(b)
+}
+
+// fuzzRoundTrip decodes `data` into `v` and checks that re-encoding
+// reproduces the consumed bytes.
+func fuzzRoundTrip(t *testing.T, data []byte, v interface{}) {
+  n, err := Unmarshal(bytes.NewReader(data), v)
+  if err != nil {
+    return
+  }
+  var b bytes.Buffer
+  if _, err := Marshal(&b, v); err != nil {
+    t.Fatalf("re-encoding decoded value failed: %v", err)
+  }
+  if !bytes.Equal(b.Bytes(), data[:n]) {
+    t.Fatalf("round trip mismatch: %x != %x", b.Bytes(), data[:n])
+  }
+}
+{{#each namespaces as |ns|}}
+{{#each ns.typedefs as |td|}}
+
+func random{{td.def.name}}(r *rand.Rand, depth int) {{td.def.name}} {
+  var v {{td.def.name}}
+  {{go-random "v" td.def.type_name td.def.array_size td.def.fixed_array false}}
+  return v
+}
+{{> round-trip name=td.def.name}}
+{{/each}}
+{{#each ns.structs as |st|}}
+
+func random{{st.name}}(r *rand.Rand, depth int) {{st.name}} {
+  var v {{st.name}}
+{{#each st.props as |prop|}}
+  {{go-random (field "v" prop.name) prop.type_name prop.array_size prop.fixed_array false}}
+{{/each}}
+  return v
+}
+{{> round-trip name=st.name}}
+{{/each}}
+{{#each ns.enums as |enum|}}
+
+func random{{enum.name}}(r *rand.Rand, depth int) {{enum.name}} {
+  values := []{{enum.name}}{
+{{#each enum.values as |val|}}
+    {{enum.name}}{{val.name}},
+{{/each}}
+  }
+  return values[r.Intn(len(values))]
+}
+{{> round-trip name=enum.name}}
+{{/each}}
+{{#each ns.unions as |uni|}}
+
+func random{{uni.name}}(r *rand.Rand, depth int) {{uni.name}} {
+  var u {{uni.name}}
+  switch r.Intn({{len uni.switch.cases}}) {
+{{#each uni.switch.cases as |case|}}
+  case {{@index}}:
+    u.{{uni.switch.enum_name}} = {{uni.switch.enum_type}}{{case.value}}
+{{#if (not (isvoid case.ret_type.name))}}
+    var v {{go-type case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array true}}
+    {{go-random "v" case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array true}}
+    u.{{case.ret_type.name}} = &v
+{{/if}}
+{{/each}}
+  }
+  return u
+}
+{{> round-trip name=uni.name}}
+{{/each}}
+{{/each~}}
+"#;
+
+static FOOTER: &str = r#"
+// Namespace end {{ns.name}}
+{{/each~}}
+"#;
+
+#[derive(Debug, Default, Serialize)]
+pub struct GoGenerator {
+    /// Emit explicit EncodeTo/DecodeFrom methods and a generated runtime
+    /// instead of delegating to go-xdr.
+    pub standalone: bool,
+
+    /// Marshal enums to JSON and text by their names in the enum map.
+    pub enum_names: bool,
+
+    /// Marshal unions to JSON as `{"type": "<case>", "<arm>": ...}`.
+    pub union_names: bool,
+
+    /// Generate `Validate()` methods enforcing XDR bounds.
+    pub validate: bool,
+
+    /// Generate `Clone()`, `Equal()` and `Hash()` methods.
+    pub copy: bool,
+
+    /// Go package name, `xdr` if not set.
+    pub package: Option<String>,
+
+    /// Import path prefix for cross-package references when each namespace
+    /// is generated as its own package, see `GoGenerator::packages`.
+    pub import_prefix: Option<String>,
+}
+
+#[derive(Serialize)]
+struct RenderData<'a> {
+    namespaces: Vec<Namespace>,
+
+    options: &'a GoGenerator,
+
+    package: String,
+
+    imports: Vec<String>,
+
+    /// Package providing the shared encoder runtime in package mode.
+    runtime: Option<String>,
+}
+
+/// Derives a Go package name from a namespace name.
+pub fn package_name(namespace: &str) -> String {
+    namespace.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_ascii_lowercase()
+}
+
+fn def_types(namespace: &mut Namespace) -> Vec<&mut String> {
+    let mut types = Vec::new();
+    for typedef_ in &mut namespace.typedefs {
+        types.push(&mut typedef_.def.type_name);
+    }
+    for struct_ in &mut namespace.structs {
+        for prop in &mut struct_.props {
+            types.push(&mut prop.type_name);
+        }
+    }
+    for union_ in &mut namespace.unions {
+        types.push(&mut union_.switch.enum_type);
+        for case in &mut union_.switch.cases {
+            types.push(&mut case.ret_type.type_name);
+        }
+    }
+    types
+}
+
+impl GoGenerator {
+    /// Generates each namespace as its own Go package, returning the package
+    /// names and code. Types referenced from other namespaces are qualified
+    /// and imported from `import_prefix`/package, with types of the namespace
+    /// itself taking precedence. In standalone mode the encoder runtime is
+    /// returned as an extra package, named after `package`.
+    pub fn packages(&self, namespaces: Vec<Namespace>) -> Result<Vec<(String, String)>, &'static str> {
+        let prefix = self.import_prefix.as_ref().ok_or("import prefix required for package generation")?;
+        let processed_ns = process_namespaces(namespaces)?;
+        let declared: Vec<(String, Vec<String>)> = processed_ns
+            .iter()
+            .map(|namespace| {
+                let names = namespace
+                    .typedefs
+                    .iter()
+                    .map(|td| td.def.name.clone())
+                    .chain(namespace.structs.iter().map(|st| st.name.clone()))
+                    .chain(namespace.enums.iter().map(|en| en.name.clone()))
+                    .chain(namespace.unions.iter().map(|un| un.name.clone()));
+                (package_name(&namespace.name), names.collect())
+            })
+            .collect();
+        let prefix = prefix.trim_end_matches('/');
+        let mut packages = Vec::new();
+        let runtime = match self.standalone {
+            true => {
+                let runtime = self.package.clone().unwrap_or_else(|| String::from("xdr"));
+                if declared.iter().any(|(package, _)| *package == runtime) {
+                    return Err("a namespace package has the name of the runtime package");
+                }
+                let preamble = format!("// Package {} is the XDR runtime shared by the generated packages", runtime);
+                let code = format!("{}{}", go_preamble(&preamble, &runtime, RUNTIME_T, &[]), RUNTIME_T);
+                packages.push((runtime.clone(), code));
+                Some(runtime)
+            }
+            false => None,
+        };
+        for (mut namespace, (package, local)) in processed_ns.into_iter().zip(&declared) {
+            let mut imports = Vec::new();
+            if let Some(runtime) = &runtime {
+                imports.push(format!("{}/{}", prefix, runtime));
+            }
+            for type_name in def_types(&mut namespace) {
+                if local.contains(type_name) {
+                    continue;
+                }
+                let owner = declared.iter().find(|(_, names)| names.contains(type_name));
+                if let Some((owner, _)) = owner {
+                    let import = format!("{}/{}", prefix, owner);
+                    if !imports.contains(&import) {
+                        imports.push(import);
+                    }
+                    *type_name = format!("{}.{}", owner, type_name);
+                }
+            }
+            let code = self.render(vec![namespace], package.clone(), imports, runtime.clone())?;
+            packages.push((package.clone(), code));
+        }
+        Ok(packages)
+    }
+
+    fn build_file_template(&self, shared_runtime: bool) -> String {
+        let mut file_t = String::new();
+        match (self.standalone, shared_runtime) {
+            (true, true) => file_t.push_str(SHARED_RUNTIME_HEADER),
+            (true, false) => file_t.push_str(RUNTIME_T),
+            (false, _) => file_t.push_str(HEADER),
+        }
+        if self.validate {
+            file_t.push_str(VALIDATE_HELPERS_T);
+        }
+        for section in &[NAMESPACE_START, TYPEDEFS_T, STRUCTS_T, ENUM_T, UNION_T] {
+            file_t.push_str(section);
+        }
+        if self.validate {
+            file_t.push_str(VALIDATE_T);
+        }
+        if self.copy {
+            file_t.push_str(COPY_T);
+        }
+        if self.standalone {
+            file_t.push_str(CODEC_T);
+        }
+        file_t.push_str(FOOTER);
+        file_t
+    }
+}
+
+static STD_IMPORTS: &[&str] = &[
+    "bytes",
+    "encoding",
+    "encoding/binary",
+    "encoding/json",
+    "fmt",
+    "hash/fnv",
+    "io",
+    "math",
+    "math/rand",
+    "testing",
+];
+
+/// Reports whether `code` references the package imported as `path`,
+/// ignoring comment lines.
+fn uses_package(code: &str, path: &str) -> bool {
+    let selector = format!("{}.", path.rsplit('/').next().unwrap_or(path));
+    code.lines().filter(|line| !line.trim_start().starts_with("//")).any(|line| {
+        line.match_indices(&selector).any(|(idx, _)| {
+            !line[..idx]
+                .chars()
+                .last()
+                .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        })
+    })
+}
+
+/// Renders the file comment, package clause and the imports used by `code`.
+fn go_preamble(comment: &str, package: &str, code: &str, imports: &[String]) -> String {
+    let std: Vec<&&str> = STD_IMPORTS.iter().filter(|path| uses_package(code, path)).collect();
+    let mut preamble = format!("\n{}\n// DO NOT EDIT or your changes may be overwritten\npackage {}\n", comment, package);
+    if std.is_empty() && imports.is_empty() {
+        return preamble;
+    }
+    preamble.push_str("\nimport (\n");
+    for path in &std {
+        preamble.push_str(&format!("  \"{}\"\n", path));
+    }
+    if !std.is_empty() && !imports.is_empty() {
+        preamble.push('\n');
+    }
+    for path in imports {
+        preamble.push_str(&format!("  \"{}\"\n", path));
+    }
+    preamble.push_str(")\n");
+    preamble
+}
+
+fn go_type(typ: &str, size: i64, fixed: bool, slice: bool) -> String {
+    match (typ, size) {
+        ("string", _) | (_, 0) => typ.to_string(),
+        (typ, size) if fixed && !slice => format!("[{}]{}", size, typ),
+        (typ, _) => format!("[]{}", typ),
+    }
+}
+
+fn go_primitive(typ: &str) -> Option<&'static str> {
+    match typ {
+        "int32" => Some("Int"),
+        "uint32" => Some("Uint"),
+        "int64" => Some("Hyper"),
+        "uint64" => Some("Uhyper"),
+        "float32" => Some("Float"),
+        "float64" => Some("Double"),
+        "bool" => Some("Bool"),
+        _ => None,
+    }
+}
+
+fn go_check(call: String, indent: &str) -> String {
+    format!("if err := {}; err != nil {{\n{}  return err\n{}}}", call, indent, indent)
+}
+
+fn go_max(size: i64) -> i64 {
+    match size {
+        0 => i64::from(i32::MAX),
+        size => size,
+    }
+}
+
+fn go_encode_value(value: &str, typ: &str, size: i64) -> String {
+    match (typ, go_primitive(typ)) {
+        ("string", _) => format!("e.EncodeString(string({}), {})", value, go_max(size)),
+        (typ, Some(method)) => format!("e.Encode{}({}({}))", method, typ, value),
+        _ => format!("{}.EncodeTo(e)", value),
+    }
+}
+
+fn go_decode_value(lvalue: &str, typ: &str, size: i64) -> String {
+    let pointer = match lvalue.strip_prefix("(*").and_then(|val| val.strip_suffix(')')) {
+        Some(val) => val.to_string(),
+        None => format!("&{}", lvalue),
+    };
+    match (typ, go_primitive(typ)) {
+        ("string", _) => format!("d.DecodeString((*string)({}), {})", pointer, go_max(size)),
+        (typ, Some(method)) => format!("d.Decode{}((*{})({}))", method, typ, pointer),
+        _ => format!("{}.DecodeFrom(d)", lvalue),
+    }
+}
+
+fn go_encode(value: &str, typ: &str, size: i64, fixed: bool, slice: bool) -> String {
+    let item = format!("{}[i]", value);
+    match (typ, size, fixed) {
+        ("string", _, _) | (_, 0, _) => go_check(go_encode_value(value, typ, size), "  "),
+        ("byte", size, true) if slice => go_check(format!("e.EncodeFixedOpaque({}, {})", value, size), "  "),
+        ("byte", size, true) => go_check(format!("e.EncodeFixedOpaque({}[:], {})", value, size), "  "),
+        ("byte", size, false) => go_check(format!("e.EncodeOpaque({}, {})", value, size), "  "),
+        (typ, size, fixed) => {
+            let length = match (fixed, slice) {
+                (true, true) => format!(
+                    "if len({}) != {} {{\n    return fmt.Errorf(\"xdr: array length %d does not match {}\", len({}))\n  }}\n  ",
+                    value, size, size, value
+                ),
+                (true, false) => String::new(),
+                (false, _) => format!("{}\n  ", go_check(format!("e.EncodeLen(len({}), {})", value, size), "  ")),
+            };
+            format!(
+                "{}for i := range {} {{\n    {}\n  }}",
+                length,
+                value,
+                go_check(go_encode_value(&item, typ, 0), "    ")
+            )
+        }
+    }
+}
+
+fn go_validate(value: &str, name: &str, typ: &str, size: i64, fixed: bool, slice: bool) -> String {
+    let mut checks = Vec::new();
+    let unbounded = size == i64::from(i32::MAX);
+    match (typ, size, fixed) {
+        (_, 0, _) => {}
+        ("string", _, _) | (_, _, false) if !unbounded => checks.push(format!(
+            "if len({}) > {} {{\n    return &ValidationError{{Path: \"{}\", Msg: fmt.Sprintf(\"length %d exceeds maximum {}\", len({}))}}\n  }}",
+            value, size, name, size, value
+        )),
+        (_, _, true) if slice => checks.push(format!(
+            "if len({}) != {} {{\n    return &ValidationError{{Path: \"{}\", Msg: fmt.Sprintf(\"length %d does not match {}\", len({}))}}\n  }}",
+            value, size, name, size, value
+        )),
+        _ => {}
+    }
+    let built_in = is_go_built_in(typ);
+    match (built_in, size) {
+        (true, _) => {}
+        (false, 0) => checks.push(format!(
+            "if err := {}.Validate(); err != nil {{\n    return validationAt(err, \"{}\")\n  }}",
+            value, name
+        )),
+        (false, _) => checks.push(format!(
+            "for i := range {} {{\n    if err := {}[i].Validate(); err != nil {{\n      return validationAt(err, fmt.Sprintf(\"{}[%d]\", i))\n    }}\n  }}",
+            value, value, name
+        )),
+    }
+    checks.join("\n  ")
+}
+
+fn is_go_built_in(typ: &str) -> bool {
+    matches!(typ, "string" | "byte") || go_primitive(typ).is_some()
+}
+
+/// Declares `dst` as a deep copy of the union arm `src`.
+fn go_clone_arm(dst: &str, src: &str, typ: &str, size: i64, fixed: bool) -> String {
+    match go_clone(dst, src, typ, size, fixed, true) {
+        clone if clone.is_empty() => format!("{} := {}", dst, src),
+        _ if size == 0 => format!("{} := {}.Clone()", dst, src),
+        clone => format!("{} := {}\n    {}", dst, src, clone),
+    }
+}
+
+/// Deep copies `src` into `dst`, which already holds a shallow copy of it.
+fn go_clone(dst: &str, src: &str, typ: &str, size: i64, fixed: bool, slice: bool) -> String {
+    let built_in = is_go_built_in(typ);
+    match (typ, size, fixed && !slice) {
+        ("string", _, _) => String::new(),
+        (_, 0, _) | (_, _, true) if built_in => String::new(),
+        (_, 0, _) => format!("{} = {}.Clone()", dst, src),
+        (_, _, true) => format!("for i := range {} {{\n    {}[i] = {}[i].Clone()\n  }}", src, dst, src),
+        (typ, _, false) => {
+            let copy = match built_in {
+                true => format!("copy({}, {})", dst, src),
+                false => format!("for i := range {} {{\n      {}[i] = {}[i].Clone()\n    }}", src, dst, src),
+            };
+            format!(
+                "if {} != nil {{\n    {} = make([]{}, len({}))\n    {}\n  }}",
+                src, dst, typ, src, copy
+            )
+        }
+    }
+}
+
+fn go_equal(a: &str, b: &str, typ: &str, size: i64, fixed: bool, slice: bool) -> String {
+    let built_in = is_go_built_in(typ);
+    match (typ, size, fixed && !slice) {
+        ("string", _, _) => format!("if {} != {} {{\n    return false\n  }}", a, b),
+        (_, 0, _) | (_, _, true) if built_in => format!("if {} != {} {{\n    return false\n  }}", a, b),
+        (_, 0, _) => format!("if !{}.Equal({}) {{\n    return false\n  }}", a, b),
+        ("byte", _, false) => format!("if !bytes.Equal({}, {}) {{\n    return false\n  }}", a, b),
+        (_, _, fixed_array) => {
+            let length = match fixed_array {
+                true => String::new(),
+                false => format!("if len({}) != len({}) {{\n    return false\n  }}\n  ", a, b),
+            };
+            let item = match built_in {
+                true => format!("{}[i] != {}[i]", a, b),
+                false => format!("!{}[i].Equal({}[i])", a, b),
+            };
+            format!(
+                "{}for i := range {} {{\n    if {} {{\n      return false\n    }}\n  }}",
+                length, a, item
+            )
+        }
+    }
+}
+
+fn go_random_value(typ: &str, size: i64) -> String {
+    match typ {
+        "string" => format!("randomString(r, {})", go_max(size)),
+        "byte" => String::from("byte(r.Intn(256))"),
+        "int32" => String::from("int32(r.Uint32())"),
+        "uint32" => String::from("r.Uint32()"),
+        "int64" => String::from("int64(r.Uint64())"),
+        "uint64" => String::from("r.Uint64()"),
+        "float32" => String::from("r.Float32()"),
+        "float64" => String::from("r.Float64()"),
+        "bool" => String::from("r.Intn(2) == 1"),
+        typ => format!("random{}(r, depth+1)", typ),
+    }
+}
+
+/// Fills `lvalue` with a random value within the XDR bounds of the type.
+fn go_random(lvalue: &str, typ: &str, size: i64, fixed: bool, slice: bool) -> String {
+    let item = format!("{}[i]", lvalue);
+    match (typ, size, fixed) {
+        ("string", _, _) | (_, 0, _) => format!("{} = {}", lvalue, go_random_value(typ, size)),
+        ("byte", _, true) if !slice => format!("r.Read({}[:])", lvalue),
+        ("byte", size, true) => format!("{} = make([]byte, {})\n  r.Read({})", lvalue, size, lvalue),
+        ("byte", size, false) => format!("{} = make([]byte, randomLen(r, {}, depth))\n  r.Read({})", lvalue, size, lvalue),
+        (typ, size, true) => {
+            let alloc = match slice {
+                true => format!("{} = make([]{}, {})\n  ", lvalue, typ, size),
+                false => String::new(),
+            };
+            format!(
+                "{}for i := range {} {{\n    {} = {}\n  }}",
+                alloc,
+                lvalue,
+                item,
+                go_random_value(typ, 0)
+            )
+        }
+        (typ, size, false) => format!(
+            "{} = make([]{}, randomLen(r, {}, depth))\n  for i := range {} {{\n    {} = {}\n  }}",
+            lvalue,
+            typ,
+            size,
+            lvalue,
+            item,
+            go_random_value(typ, 0)
+        ),
+    }
+}
+
+fn go_decode(lvalue: &str, typ: &str, size: i64, fixed: bool, slice: bool) -> String {
+    let item = format!("{}[i]", lvalue);
+    let pointer = match lvalue.strip_prefix("(*").and_then(|val| val.strip_suffix(')')) {
+        Some(val) => val.to_string(),
+        None => format!("&{}", lvalue),
+    };
+    match (typ, size, fixed) {
+        ("string", _, _) | (_, 0, _) => go_check(go_decode_value(lvalue, typ, size), "  "),
+        ("byte", size, true) if slice => format!(
+            "{} = make([]byte, {})\n  {}",
+            lvalue,
+            size,
+            go_check(format!("d.DecodeFixedOpaque({})", lvalue), "  ")
+        ),
+        ("byte", _, true) => go_check(format!("d.DecodeFixedOpaque({}[:])", lvalue), "  "),
+        ("byte", size, false) => go_check(format!("d.DecodeOpaque((*[]byte)({}), {})", pointer, size), "  "),
+        (typ, size, true) => {
+            let alloc = match slice {
+                true => format!("{} = make([]{}, {})\n  ", lvalue, typ, size),
+                false => String::new(),
+            };
+            format!(
+                "{}for i := range {} {{\n    {}\n  }}",
+                alloc,
+                lvalue,
+                go_check(go_decode_value(&item, typ, 0), "    ")
+            )
+        }
+        (typ, size, false) => format!(
+            "{{\n    n, err := d.DecodeLen({})\n    if err != nil {{\n      return err\n    }}\n    {} = make([]{}, 0, d.SliceCap(n))\n    for i := 0; i < n; i++ {{\n      var item {}\n      {}\n      {} = append({}, item)\n    }}\n  }}",
+            size,
+            lvalue,
+            typ,
+            typ,
+            go_check(go_decode_value("item", typ, 0), "      "),
+            lvalue,
+            lvalue
+        ),
+    }
+}
+
+fn process_namespaces(namespaces: Vec<Namespace>) -> Result<Vec<Namespace>, &'static str> {
+    let mut type_map = HashMap::new();
+    type_map.insert("boolean", "bool");
+    type_map.insert("opaque", "byte");
+    type_map.insert("int", "int32");
+    type_map.insert("unsigned int", "uint32");
+    type_map.insert("hyper", "int64");
+    type_map.insert("unsigned hyper", "uint64");
+    type_map.insert("float", "float32");
+    type_map.insert("double", "float64");
     let mut ret_val = apply_type_map(namespaces, &type_map)?;
     for namespace in &mut ret_val {
         for typedef_ in &mut namespace.typedefs {
@@ -414,32 +1807,113 @@ fn process_namespaces(namespaces: Vec<Namespace>) -> Result<Vec<Namespace>, &'st
     Ok(ret_val)
 }
 
-fn to_first_lower(value: &str) -> String {
-    let mut c = value.chars();
-    match c.next() {
-        None => String::new(),
-        Some(f) => f.to_lowercase().collect::<String>() + c.as_str(),
+impl GoGenerator {
+    /// Generates a `_test.go` file with fuzz targets and random value
+    /// round-trip tests for every generated type.
+    pub fn tests(&self, namespaces: Vec<Namespace>) -> Result<String, &'static str> {
+        let data = RenderData {
+            namespaces: process_namespaces(namespaces)?,
+            options: self,
+            package: self.package.clone().unwrap_or_else(|| String::from("xdr")),
+            imports: Vec::new(),
+            runtime: None,
+        };
+        let body = self.render_body(TEST_T, &data)?;
+        let preamble = format!("// Package {} tests are automatically generated", data.package);
+        Ok(format!("{}{}", go_preamble(&preamble, &data.package, &body, &[]), body))
     }
-}
 
-impl CodeGenerator for GoGenerator {
-    fn code(&self, namespaces: Vec<Namespace>) -> Result<String, &'static str> {
+    /// Fails if a struct field or union arm has the name of a method generated
+    /// on its type, which Go does not allow.
+    fn check_methods(&self, processed_ns: &[Namespace]) -> Result<(), &'static str> {
+        for ns in processed_ns {
+            let fields = ns
+                .structs
+                .iter()
+                .flat_map(|st| st.props.iter().map(|prop| prop.name.as_str()))
+                .chain(ns.unions.iter().flat_map(|un| {
+                    std::iter::once(un.switch.enum_name.as_str()).chain(un.switch.cases.iter().map(|case| case.ret_type.name.as_str()))
+                }));
+            for field in fields {
+                if self.validate && field == "Validate" {
+                    return Err("a field or arm named validate clashes with the generated Validate method");
+                }
+                if self.copy && matches!(field, "Clone" | "Equal" | "Hash") {
+                    return Err("a field or arm named clone, equal or hash clashes with the generated method");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn render(
+        &self,
+        processed_ns: Vec<Namespace>,
+        package: String,
+        imports: Vec<String>,
+        runtime: Option<String>,
+    ) -> Result<String, &'static str> {
+        self.check_methods(&processed_ns)?;
+        let file_t = self.build_file_template(runtime.is_some());
+        let data = RenderData {
+            namespaces: processed_ns,
+            options: self,
+            package,
+            imports,
+            runtime,
+        };
+        let body = self.render_body(&file_t, &data)?;
+        let mut imports = data.imports;
+        if !self.standalone {
+            imports.insert(0, String::from("github.com/stellar/go-xdr/xdr3"));
+        }
+        let preamble = format!("// Package {} is automatically generated", data.package);
+        Ok(format!("{}{}", go_preamble(&preamble, &data.package, &body, &imports), body))
+    }
+
+    fn render_body(&self, file_t: &str, data: &RenderData) -> Result<String, &'static str> {
         let mut reg = Handlebars::new();
-        let file_t = build_file_template();
         handlebars_helper!(neqstr: |x: str| x != "string");
         handlebars_helper!(eqstr: |x: str| x == "string");
         handlebars_helper!(bignum: |x: str| x == "uint64" || x =="int64");
-        handlebars_helper!(isvoid: |x: str| x == "");
+        handlebars_helper!(isvoid: |x: str| x.is_empty());
         handlebars_helper!(lower: |x: str| to_first_lower(x));
+        handlebars_helper!(doc: |comment: str, prefix: str| doc_comment(comment, prefix));
+        reg.register_helper("doc", Box::new(doc));
         reg.register_helper("neqstr", Box::new(neqstr));
         reg.register_helper("eqstr", Box::new(eqstr));
         reg.register_helper("bignum", Box::new(bignum));
         reg.register_helper("isvoid", Box::new(isvoid));
-        let processed_ns = process_namespaces(namespaces)?;
         reg.register_helper("lower", Box::new(lower));
-        let result = reg.render_template(file_t.into_boxed_str().as_ref(), &processed_ns).unwrap();
+        handlebars_helper!(field: |owner: str, name: str| format!("{}.{}", owner, name));
+        handlebars_helper!(deref: |owner: str, name: str| format!("(*{}.{})", owner, name));
+        handlebars_helper!(gotype: |typ: str, size: i64, fixed: bool, slice: bool| go_type(typ, size, fixed, slice));
+        handlebars_helper!(goencode: |value: str, typ: str, size: i64, fixed: bool, slice: bool| go_encode(value, typ, size, fixed, slice));
+        handlebars_helper!(govalidate: |value: str, name: str, typ: str, size: i64, fixed: bool, slice: bool| go_validate(value, name, typ, size, fixed, slice));
+        handlebars_helper!(godecode: |lvalue: str, typ: str, size: i64, fixed: bool, slice: bool| go_decode(lvalue, typ, size, fixed, slice));
+        reg.register_helper("field", Box::new(field));
+        reg.register_helper("deref", Box::new(deref));
+        reg.register_helper("go-type", Box::new(gotype));
+        reg.register_helper("go-encode", Box::new(goencode));
+        reg.register_helper("go-decode", Box::new(godecode));
+        reg.register_helper("go-validate", Box::new(govalidate));
+        handlebars_helper!(goclone: |dst: str, src: str, typ: str, size: i64, fixed: bool, slice: bool| go_clone(dst, src, typ, size, fixed, slice));
+        handlebars_helper!(goequal: |a: str, b: str, typ: str, size: i64, fixed: bool, slice: bool| go_equal(a, b, typ, size, fixed, slice));
+        handlebars_helper!(goclonearm: |dst: str, src: str, typ: str, size: i64, fixed: bool| go_clone_arm(dst, src, typ, size, fixed));
+        reg.register_helper("go-clone", Box::new(goclone));
+        reg.register_helper("go-clone-arm", Box::new(goclonearm));
+        reg.register_helper("go-equal", Box::new(goequal));
+        handlebars_helper!(gorandom: |lvalue: str, typ: str, size: i64, fixed: bool, slice: bool| go_random(lvalue, typ, size, fixed, slice));
+        reg.register_helper("go-random", Box::new(gorandom));
+        reg.register_escape_fn(handlebars::no_escape);
+        Ok(reg.render_template(file_t, data).unwrap())
+    }
+}
 
-        Ok(result)
+impl CodeGenerator for GoGenerator {
+    fn code(&self, namespaces: Vec<Namespace>) -> Result<String, &'static str> {
+        let package = self.package.clone().unwrap_or_else(|| String::from("xdr"));
+        self.render(process_namespaces(namespaces)?, package, Vec::new(), None)
     }
 }
 
@@ -455,7 +1929,7 @@ mod tests {
             unions: Vec::new(),
             name: String::from("test"),
         }];
-        let res = GoGenerator {}.code(input_test);
+        let res = GoGenerator::default().code(input_test);
         assert!(res.is_ok());
     }
     #[test]
@@ -470,12 +1944,13 @@ mod tests {
                     array_size: 0,
                     fixed_array: false,
                     tag: String::new(),
+                    comment: String::new(),
                 },
             }],
             unions: Vec::new(),
             name: String::from("test"),
         }];
-        let res = GoGenerator {}.code(input_test);
+        let res = GoGenerator::default().code(input_test);
         assert!(res.is_ok());
         let generated_code = res.unwrap();
         assert!(generated_code.contains("func (s Testt) MarshalBinary() ([]byte, error)"));
@@ -495,6 +1970,7 @@ mod tests {
                         array_size: 0,
                         fixed_array: false,
                         tag: String::new(),
+                        comment: String::new(),
                     },
                     Def {
                         name: String::from("BooleanTest"),
@@ -502,6 +1978,7 @@ mod tests {
                         array_size: 0,
                         fixed_array: false,
                         tag: String::new(),
+                        comment: String::new(),
                     },
                     Def {
                         name: String::from("float_test"),
@@ -509,6 +1986,7 @@ mod tests {
                         array_size: 0,
                         fixed_array: false,
                         tag: String::new(),
+                        comment: String::new(),
                     },
                     Def {
                         name: String::from("int_test"),
@@ -516,6 +1994,7 @@ mod tests {
                         array_size: 0,
                         fixed_array: false,
                         tag: String::new(),
+                        comment: String::new(),
                     },
                     Def {
                         name: String::from("unsigned_int_test"),
@@ -523,6 +2002,7 @@ mod tests {
                         array_size: 0,
                         fixed_array: false,
                         tag: String::new(),
+                        comment: String::new(),
                     },
                     Def {
                         name: String::from("hyper_test"),
@@ -530,6 +2010,7 @@ mod tests {
                         array_size: 0,
                         fixed_array: false,
                         tag: String::new(),
+                        comment: String::new(),
                     },
                     Def {
                         name: String::from("unsigned_hyper_test"),
@@ -537,14 +2018,16 @@ mod tests {
                         array_size: 0,
                         fixed_array: false,
                         tag: String::new(),
+                        comment: String::new(),
                     },
                 ],
                 tag: String::new(),
+                comment: String::new(),
             }],
             unions: Vec::new(),
             name: String::from("test"),
         }];
-        let res = GoGenerator {}.code(input_test);
+        let res = GoGenerator::default().code(input_test);
         assert!(res.is_ok());
         let generated_code = res.unwrap();
         assert!(generated_code.contains("type TestStruct struct {"));
@@ -569,8 +2052,10 @@ mod tests {
                     array_size: 0,
                     fixed_array: false,
                     tag: String::new(),
+                    comment: String::new(),
                 }],
                 tag: String::new(),
+                comment: String::new(),
             }],
             typedefs: Vec::new(),
             unions: vec![Union {
@@ -587,7 +2072,9 @@ mod tests {
                                 array_size: 0,
                                 fixed_array: false,
                                 tag: String::new(),
+                                comment: String::new(),
                             },
+                            comment: String::new(),
                         },
                         Case {
                             value: String::from("TWO"),
@@ -597,7 +2084,9 @@ mod tests {
                                 array_size: 0,
                                 fixed_array: false,
                                 tag: String::new(),
+                                comment: String::new(),
                             },
+                            comment: String::new(),
                         },
                         Case {
                             value: String::from("THREE"),
@@ -607,7 +2096,9 @@ mod tests {
                                 array_size: 5,
                                 fixed_array: false,
                                 tag: String::new(),
+                                comment: String::new(),
                             },
+                            comment: String::new(),
                         },
                         Case {
                             value: String::from("FOUR"),
@@ -617,14 +2108,17 @@ mod tests {
                                 array_size: 5,
                                 fixed_array: false,
                                 tag: String::new(),
+                                comment: String::new(),
                             },
+                            comment: String::new(),
                         },
                     ],
                 },
+                comment: String::new(),
             }],
             name: String::from("test"),
         }];
-        let res = GoGenerator {}.code(input_test);
+        let res = GoGenerator::default().code(input_test);
         assert!(res.is_ok());
         let generated_code = res.unwrap();
         println!("{}", generated_code);
@@ -656,4 +2150,602 @@ mod tests {
         assert!(generated_code.contains("func (u TestUnion) GetArrayStructTest() (result []TestStruct, ok bool) {"));
         assert!(generated_code.contains("result.ArrayStructTest = &tv"));
     }
+
+    #[test]
+    fn standalone() {
+        let input_test = vec![Namespace {
+            enums: Vec::new(),
+            structs: vec![Struct {
+                name: String::from("TestStruct"),
+                props: vec![
+                    Def {
+                        name: String::from("name"),
+                        type_name: String::from("string"),
+                        array_size: 256,
+                        fixed_array: false,
+                        tag: String::new(),
+                        comment: String::new(),
+                    },
+                    Def {
+                        name: String::from("hash"),
+                        type_name: String::from("opaque"),
+                        array_size: 32,
+                        fixed_array: true,
+                        tag: String::new(),
+                        comment: String::new(),
+                    },
+                    Def {
+                        name: String::from("values"),
+                        type_name: String::from("int"),
+                        array_size: 2147483647,
+                        fixed_array: false,
+                        tag: String::new(),
+                        comment: String::new(),
+                    },
+                ],
+                tag: String::new(),
+                comment: String::new(),
+            }],
+            typedefs: Vec::new(),
+            unions: Vec::new(),
+            name: String::from("test"),
+        }];
+        let res = GoGenerator {
+            standalone: true,
+            ..Default::default()
+        }
+        .code(input_test);
+        assert!(res.is_ok());
+        let generated_code = res.unwrap();
+        assert!(!generated_code.contains("github.com/stellar/go-xdr/xdr3"));
+        assert!(generated_code.contains("type Encoder struct {"));
+        assert!(generated_code.contains("func (s TestStruct) EncodeTo(e *Encoder) error {"));
+        assert!(generated_code.contains("func (s *TestStruct) DecodeFrom(d *Decoder) error {"));
+        assert!(generated_code.contains("e.EncodeString(string(s.Name), 256)"));
+        assert!(generated_code.contains("e.EncodeFixedOpaque(s.Hash[:], 32)"));
+        assert!(generated_code.contains("d.DecodeFixedOpaque(s.Hash[:])"));
+        // Lengths read from the input only bound how far slices grow.
+        assert!(generated_code.contains("s.Values = make([]int32, 0, d.SliceCap(n))"));
+        assert!(generated_code.contains("s.Values = append(s.Values, item)"));
+        assert!(generated_code.contains("b, err := d.readBytes(n)"));
+    }
+
+    #[test]
+    fn package_name_option() {
+        let input_test = vec![Namespace {
+            enums: Vec::new(),
+            structs: Vec::new(),
+            typedefs: Vec::new(),
+            unions: Vec::new(),
+            name: String::from("test"),
+        }];
+        let res = GoGenerator {
+            package: Some(String::from("chain")),
+            ..Default::default()
+        }
+        .code(input_test);
+        assert!(res.is_ok());
+        let generated_code = res.unwrap();
+        assert!(generated_code.contains("package chain\n"));
+        assert!(!generated_code.contains("package xdr"));
+    }
+
+    #[test]
+    fn packages() {
+        let input_test = vec![
+            Namespace {
+                enums: Vec::new(),
+                structs: Vec::new(),
+                typedefs: vec![Typedef {
+                    def: Def {
+                        name: String::from("Hash"),
+                        type_name: String::from("opaque"),
+                        array_size: 32,
+                        fixed_array: true,
+                        tag: String::new(),
+                        comment: String::new(),
+                    },
+                }],
+                unions: Vec::new(),
+                name: String::from("common"),
+            },
+            Namespace {
+                enums: Vec::new(),
+                structs: vec![Struct {
+                    name: String::from("Block"),
+                    props: vec![Def {
+                        name: String::from("parent"),
+                        type_name: String::from("Hash"),
+                        array_size: 0,
+                        fixed_array: false,
+                        tag: String::new(),
+                        comment: String::new(),
+                    }],
+                    tag: String::new(),
+                    comment: String::new(),
+                }],
+                typedefs: Vec::new(),
+                unions: Vec::new(),
+                name: String::from("chain"),
+            },
+        ];
+        let res = GoGenerator {
+            standalone: true,
+            import_prefix: Some(String::from("github.com/acme/xdr")),
+            ..Default::default()
+        }
+        .packages(input_test);
+        assert!(res.is_ok());
+        let packages = res.unwrap();
+        let names: Vec<&str> = packages.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["xdr", "common", "chain"]);
+        assert!(packages[0].1.contains("type Encoder struct {"));
+        assert!(packages[1].1.contains("package common\n"));
+        assert!(packages[1].1.contains("type Encoder = xdr.Encoder"));
+        assert!(!packages[1].1.contains("\"github.com/acme/xdr/common\""));
+        assert!(packages[2].1.contains("\"github.com/acme/xdr/common\""));
+        assert!(packages[2].1.contains("Parent common.Hash `json:\"parent\"`"));
+    }
+
+    #[test]
+    fn packages_resolve_locally() {
+        let schema = "namespace other
+{
+    struct A
+    {
+        hyper y;
+    };
+
+    struct B
+    {
+        A a;
+    };
+}
+
+namespace xdr
+{
+    struct A
+    {
+        int x;
+    };
+
+    struct C
+    {
+        B b;
+    };
+}";
+        let generator = GoGenerator {
+            standalone: true,
+            import_prefix: Some(String::from("example.com/p")),
+            ..Default::default()
+        };
+        let res = generator.packages(build_namespaces(String::from(schema)).unwrap());
+        assert_eq!(res.unwrap_err(), "a namespace package has the name of the runtime package");
+        let generator = GoGenerator {
+            package: Some(String::from("runtime")),
+            ..generator
+        };
+        let packages = generator.packages(build_namespaces(String::from(schema)).unwrap()).unwrap();
+        assert!(packages[1].1.contains("A A `json:\"a\"`"));
+        assert!(!packages[1].1.contains("\"example.com/p/xdr\""));
+        assert!(packages[2].1.contains("B other.B `json:\"b\"`"));
+    }
+
+    #[test]
+    fn enum_names() {
+        let input_test = vec![Namespace {
+            enums: vec![Enum {
+                name: String::from("Color"),
+                values: vec![EnumValue {
+                    name: String::from("RED"),
+                    index: 0,
+                    comment: String::new(),
+                }],
+                comment: String::new(),
+            }],
+            structs: Vec::new(),
+            typedefs: Vec::new(),
+            unions: Vec::new(),
+            name: String::from("test"),
+        }];
+        let res = GoGenerator {
+            enum_names: true,
+            ..Default::default()
+        }
+        .code(input_test);
+        assert!(res.is_ok());
+        let generated_code = res.unwrap();
+        assert!(generated_code.contains("func (s Color) MarshalText() ([]byte, error) {"));
+        assert!(generated_code.contains("func (s *Color) UnmarshalText(text []byte) error {"));
+        assert!(generated_code.contains("func (s Color) MarshalJSON() ([]byte, error) {"));
+        assert!(generated_code.contains("func (s *Color) UnmarshalJSON(data []byte) error {"));
+        assert!(generated_code.contains("var ColorNames = map[int32]string{\n  0: \"RED\",\n}"));
+        assert!(generated_code.contains("name, ok := ColorNames[int32(s)]"));
+    }
+
+    #[test]
+    fn enum_names_encode() {
+        let namespaces = build_namespaces(String::from(
+            "namespace test
+{
+    enum Kind
+    {
+        CALL = 0,
+        NONE = 5
+    };
+}",
+        ))
+        .unwrap();
+        let generated_code = GoGenerator {
+            enum_names: true,
+            ..Default::default()
+        }
+        .code(namespaces)
+        .unwrap();
+        // Marshalled by schema name, not by the prefixed KindMap name.
+        assert!(generated_code.contains("var KindNames = map[int32]string{\n  0: \"CALL\",\n  5: \"NONE\",\n}"));
+        assert!(generated_code.contains(
+            "name, ok := KindNames[int32(s)]\n  if !ok {\n    return nil, fmt.Errorf(\"invalid Kind value %d\", int32(s))\n  }\n  return []byte(name), nil"
+        ));
+        assert!(generated_code.contains("text, err := s.MarshalText()"));
+        assert!(generated_code.contains("return json.Marshal(string(text))"));
+        // Unmarshalled from a schema name, a KindMap name or an integer.
+        assert!(generated_code.contains("for value, name := range KindNames {"));
+        assert!(generated_code.contains("if name == string(text) || KindMap[value] == string(text) {"));
+        assert!(generated_code.contains(
+            "var value int32\n  if err := json.Unmarshal(text, &value); err != nil {\n    return fmt.Errorf(\"invalid Kind name %q\", text)\n  }"
+        ));
+        assert!(generated_code.contains("if _, ok := KindMap[value]; !ok {"));
+        assert!(generated_code.contains(
+            "if err := json.Unmarshal(data, &name); err == nil {\n    return s.UnmarshalText([]byte(name))\n  }\n  return s.UnmarshalText(data)"
+        ));
+    }
+
+    #[test]
+    fn validate() {
+        let input_test = vec![Namespace {
+            enums: Vec::new(),
+            structs: vec![Struct {
+                name: String::from("TestStruct"),
+                props: vec![
+                    Def {
+                        name: String::from("name"),
+                        type_name: String::from("string"),
+                        array_size: 256,
+                        fixed_array: false,
+                        tag: String::new(),
+                        comment: String::new(),
+                    },
+                    Def {
+                        name: String::from("children"),
+                        type_name: String::from("Child"),
+                        array_size: 4,
+                        fixed_array: false,
+                        tag: String::new(),
+                        comment: String::new(),
+                    },
+                ],
+                tag: String::new(),
+                comment: String::new(),
+            }],
+            typedefs: Vec::new(),
+            unions: Vec::new(),
+            name: String::from("test"),
+        }];
+        let res = GoGenerator {
+            validate: true,
+            ..Default::default()
+        }
+        .code(input_test.clone());
+        assert!(res.is_ok());
+        let generated_code = res.unwrap();
+        assert!(generated_code.contains("type ValidationError struct {"));
+        assert!(generated_code.contains("func (s TestStruct) Validate() error {"));
+        assert!(generated_code.contains("if len(s.Name) > 256 {"));
+        assert!(generated_code.contains("if len(s.Children) > 4 {"));
+        assert!(generated_code.contains("if err := s.Children[i].Validate(); err != nil {"));
+        let generated_code = GoGenerator::default().code(input_test).unwrap();
+        assert!(!generated_code.contains("Validate() error"));
+    }
+
+    #[test]
+    fn validate_clash() {
+        let namespaces = build_namespaces(String::from(
+            "namespace test
+{
+    struct Check
+    {
+        int validate;
+    };
+}",
+        ))
+        .unwrap();
+        assert!(GoGenerator::default().code(namespaces.clone()).is_ok());
+        let res = GoGenerator {
+            validate: true,
+            ..Default::default()
+        }
+        .code(namespaces);
+        assert_eq!(res, Err("a field or arm named validate clashes with the generated Validate method"));
+    }
+
+    #[test]
+    fn used_imports() {
+        let input_test = vec![Namespace {
+            enums: Vec::new(),
+            structs: Vec::new(),
+            typedefs: vec![Typedef {
+                def: Def {
+                    name: String::from("Hash"),
+                    type_name: String::from("opaque"),
+                    array_size: 32,
+                    fixed_array: true,
+                    tag: String::new(),
+                    comment: String::new(),
+                },
+            }],
+            unions: Vec::new(),
+            name: String::from("test"),
+        }];
+        let res = GoGenerator::default().code(input_test);
+        assert!(res.is_ok());
+        let generated_code = res.unwrap();
+        assert!(!generated_code.contains("fmtTest"));
+        assert!(!generated_code.contains("\"encoding/json\""));
+        assert!(!generated_code.contains("\"fmt\""));
+        assert!(generated_code.contains("\"bytes\""));
+        assert!(generated_code.contains("\"github.com/stellar/go-xdr/xdr3\""));
+    }
+
+    #[test]
+    fn uses_package_ignores_comments_and_selectors() {
+        assert!(uses_package("  return fmt.Errorf(\"x\")", "fmt"));
+        assert!(!uses_package("// implements json.Marshaler", "encoding/json"));
+        assert!(!uses_package("  s.Bytes.Validate()", "bytes"));
+        assert!(uses_package("  binary.BigEndian.PutUint32(b, v)", "encoding/binary"));
+    }
+
+    #[test]
+    fn union_names() {
+        let input_test = vec![Namespace {
+            enums: Vec::new(),
+            structs: Vec::new(),
+            typedefs: Vec::new(),
+            unions: vec![Union {
+                name: String::from("TestUnion"),
+                switch: Switch {
+                    enum_name: String::from("Type"),
+                    enum_type: String::from("EnumType"),
+                    cases: vec![
+                        Case {
+                            value: String::from("CALL"),
+                            ret_type: Def {
+                                name: String::from("call"),
+                                type_name: String::from("Call"),
+                                array_size: 0,
+                                fixed_array: false,
+                                tag: String::new(),
+                                comment: String::new(),
+                            },
+                            comment: String::new(),
+                        },
+                        Case {
+                            value: String::from("NONE"),
+                            ret_type: Def::default(),
+                            comment: String::new(),
+                        },
+                    ],
+                },
+                comment: String::new(),
+            }],
+            name: String::from("test"),
+        }];
+        let res = GoGenerator {
+            union_names: true,
+            ..Default::default()
+        }
+        .code(input_test);
+        assert!(res.is_ok());
+        let generated_code = res.unwrap();
+        assert!(generated_code.contains("Call *Call `json:\"call\"`"));
+        assert!(generated_code.contains("}{\"CALL\", u.Call})"));
+        assert!(generated_code.contains("case \"NONE\":"));
+        assert!(!generated_code.contains("Data interface{} `json:\"data\"`"));
+    }
+
+    #[test]
+    fn clone_equal_hash() {
+        let input_test = vec![Namespace {
+            enums: Vec::new(),
+            structs: vec![Struct {
+                name: String::from("TestStruct"),
+                props: vec![
+                    Def {
+                        name: String::from("children"),
+                        type_name: String::from("Child"),
+                        array_size: 4,
+                        fixed_array: false,
+                        tag: String::new(),
+                        comment: String::new(),
+                    },
+                    Def {
+                        name: String::from("data"),
+                        type_name: String::from("opaque"),
+                        array_size: 64,
+                        fixed_array: false,
+                        tag: String::new(),
+                        comment: String::new(),
+                    },
+                ],
+                tag: String::new(),
+                comment: String::new(),
+            }],
+            typedefs: Vec::new(),
+            unions: Vec::new(),
+            name: String::from("test"),
+        }];
+        let res = GoGenerator {
+            copy: true,
+            ..Default::default()
+        }
+        .code(input_test.clone());
+        assert!(res.is_ok());
+        let generated_code = res.unwrap();
+        assert!(generated_code.contains("func (s TestStruct) Clone() TestStruct {"));
+        assert!(generated_code.contains("c.Children[i] = s.Children[i].Clone()"));
+        assert!(generated_code.contains("copy(c.Data, s.Data)"));
+        assert!(generated_code.contains("func (s TestStruct) Equal(other TestStruct) bool {"));
+        assert!(generated_code.contains("if !bytes.Equal(s.Data, other.Data) {"));
+        assert!(generated_code.contains("func (s TestStruct) Hash() (uint64, error) {"));
+        assert!(generated_code.contains("\"hash/fnv\""));
+        let generated_code = GoGenerator::default().code(input_test).unwrap();
+        assert!(!generated_code.contains("Clone()"));
+        assert!(!generated_code.contains("\"hash/fnv\""));
+    }
+
+    #[test]
+    fn union_clone() {
+        let namespaces = build_namespaces(String::from(
+            "namespace test
+{
+    enum Kind
+    {
+        CALL = 0,
+        DATA = 1,
+        NONE = 2
+    };
+
+    union Action switch (Kind Type)
+    {
+        case CALL:
+            Call call;
+        case DATA:
+            opaque data<64>;
+        case NONE:
+            void;
+    }
+}",
+        ))
+        .unwrap();
+        let generated_code = GoGenerator {
+            copy: true,
+            ..Default::default()
+        }
+        .code(namespaces)
+        .unwrap();
+        assert!(generated_code.contains("  if u.Call != nil {\n    v := (*u.Call).Clone()\n    c.Call = &v\n  }"));
+        assert!(generated_code.contains("    v := (*u.Data)\n    if (*u.Data) != nil {"));
+    }
+
+    #[test]
+    fn copy_clash() {
+        let namespaces = build_namespaces(String::from(
+            "namespace test
+{
+    typedef opaque Hash[32];
+
+    struct Item
+    {
+        Hash hash;
+    };
+}",
+        ))
+        .unwrap();
+        assert!(GoGenerator::default().code(namespaces.clone()).is_ok());
+        let res = GoGenerator {
+            copy: true,
+            ..Default::default()
+        }
+        .code(namespaces);
+        assert_eq!(res, Err("a field or arm named clone, equal or hash clashes with the generated method"));
+    }
+
+    #[test]
+    fn tests() {
+        let input_test = vec![Namespace {
+            enums: Vec::new(),
+            structs: vec![Struct {
+                name: String::from("TestStruct"),
+                props: vec![
+                    Def {
+                        name: String::from("children"),
+                        type_name: String::from("Child"),
+                        array_size: 4,
+                        fixed_array: false,
+                        tag: String::new(),
+                        comment: String::new(),
+                    },
+                    Def {
+                        name: String::from("data"),
+                        type_name: String::from("opaque"),
+                        array_size: 32,
+                        fixed_array: true,
+                        tag: String::new(),
+                        comment: String::new(),
+                    },
+                ],
+                tag: String::new(),
+                comment: String::new(),
+            }],
+            typedefs: Vec::new(),
+            unions: Vec::new(),
+            name: String::from("test"),
+        }];
+        let res = GoGenerator::default().tests(input_test.clone());
+        assert!(res.is_ok());
+        let generated_code = res.unwrap();
+        assert!(generated_code.contains("if !bytes.Equal(b, e) {"));
+        assert!(!generated_code.contains("v.Equal(d)"));
+        assert!(generated_code.contains("func FuzzTestStruct(f *testing.F) {"));
+        assert!(generated_code.contains("func TestRoundTripTestStruct(t *testing.T) {"));
+        assert!(generated_code.contains("v.Children = make([]Child, randomLen(r, 4, depth))"));
+        assert!(generated_code.contains("v.Children[i] = randomChild(r, depth+1)"));
+        assert!(generated_code.contains("r.Read(v.Data[:])"));
+        assert!(generated_code.contains("\"math/rand\""));
+        assert!(!generated_code.contains("go-xdr"));
+        let generated_code = GoGenerator {
+            copy: true,
+            ..Default::default()
+        }
+        .tests(input_test)
+        .unwrap();
+        assert!(generated_code.contains("if !v.Equal(d) {"));
+    }
+
+    #[test]
+    fn comments() {
+        let input_test = vec![Namespace {
+            enums: vec![Enum {
+                name: String::from("Color"),
+                values: vec![EnumValue {
+                    name: String::from("RED"),
+                    index: 0,
+                    comment: String::from("The red color."),
+                }],
+                comment: String::new(),
+            }],
+            structs: vec![Struct {
+                name: String::from("TestStruct"),
+                props: vec![Def {
+                    name: String::from("count"),
+                    type_name: String::from("int"),
+                    array_size: 0,
+                    fixed_array: false,
+                    tag: String::new(),
+                    comment: String::from("Item count."),
+                }],
+                tag: String::new(),
+                comment: String::from("A test struct.\nSpans two lines."),
+            }],
+            typedefs: Vec::new(),
+            unions: Vec::new(),
+            name: String::from("test"),
+        }];
+        let res = GoGenerator::default().code(input_test);
+        assert!(res.is_ok());
+        let generated_code = res.unwrap();
+        assert!(generated_code.contains("// TestStruct generated struct\n//\n// A test struct.\n// Spans two lines.\ntype TestStruct struct {\n"));
+        assert!(generated_code.contains("  // Item count.\n"));
+        assert!(generated_code.contains("  // ColorRED enum value 0\n  //\n  // The red color.\n"));
+    }
 }
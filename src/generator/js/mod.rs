@@ -12,7 +12,7 @@ import types from 'xdr-js-serialize'
 static TYPEDEFS_T: &str = r#"
 // Start typedef section
 {{#each ns.typedefs as |td|}}
-export function {{td.def.name}}() {
+{{js-doc td.def.comment null "name"}}export function {{td.def.name}}() {
     return {{#typeconv td.def.name td.def.type_name td.def.array_size td.def.fixed_array}}{{/typeconv}}
 }
 {{/each~}}
@@ -22,7 +22,7 @@ export function {{td.def.name}}() {
 static STRUCTS_T: &str = r#"
 // Start struct section
 {{#each ns.structs as |st| ~}}
-export function {{st.name}}() {
+{{js-doc st.comment st.props "name"}}export function {{st.name}}() {
     return new types.Struct(
         [{{#each st.props as |prop| ~}}"{{prop.name}}",{{/each ~}}],
         [{{#each st.props as |prop| ~}}{{#typeconv prop.name prop.type_name prop.array_size prop.fixed_array}}{{/typeconv}},{{/each ~}}]
@@ -35,7 +35,7 @@ export function {{st.name}}() {
 static ENUM_T: &str = r#"
 // Start enum section
 {{#each ns.enums as |enum|}}
-export function {{enum.name}}() {
+{{js-doc enum.comment enum.values "name"}}export function {{enum.name}}() {
     return new types.Enum({
         {{#each enum.values as |val| ~}}
           {{val.index}}: "{{val.name}}",
@@ -51,7 +51,7 @@ static UNION_T: &str = r#"
 // Start union section
 
 {{#each ns.unions as |uni|}}
-export function {{uni.name}}() {
+{{js-doc uni.comment uni.switch.cases "value"}}export function {{uni.name}}() {
     return new types.Union(
         {{uni.switch.enum_type}}(),
         {
@@ -82,26 +82,14 @@ fn build_file_template() -> String {
 }
 
 fn is_array_type(def_type: &str) -> bool {
-    match def_type {
-        "Str" => true,
-        "opaque" => true,
-        _ => false,
-    }
+    matches!(def_type, "Str" | "opaque")
 }
 
 fn is_built_in(def_type: &str) -> bool {
-    match def_type {
-        "Void" => true,
-        "Bool" => true,
-        "Int" => true,
-        "Hyper" => true,
-        "UInt" => true,
-        "UHyper" => true,
-        "Float" => true,
-        "Double" => true,
-        "quadruple" => true,
-        _ => false,
-    }
+    matches!(
+        def_type,
+        "Void" | "Bool" | "Int" | "Hyper" | "UInt" | "UHyper" | "Float" | "Double" | "quadruple"
+    )
 }
 
 fn is_built_in_single(def_type: &str) -> bool {
@@ -135,12 +123,14 @@ impl CodeGenerator for JsGenerator {
             (_, typ, size, _) if !is_array_type(typ) && size > 0 => format!("new types.FixedArray({}, {})", size, typ),
             _ => format!("new types.{}('', {})", typ, size)
         });
-        handlebars_helper!(isvoid: |x: str| x == "");
+        handlebars_helper!(isvoid: |x: str| x.is_empty());
+        handlebars_helper!(jsdoc: |comment: str, members: Json, key: str| js_doc(comment, members.as_array().map_or(&[], |m| m.as_slice()), key));
+        reg.register_helper("js-doc", Box::new(jsdoc));
         reg.register_helper("isvoid", Box::new(isvoid));
         reg.register_helper("typeconv", Box::new(typeconv));
         reg.register_escape_fn(|s| s.into());
         let result = reg.render_template(file_t.into_boxed_str().as_ref(), &processed).unwrap();
 
-        return Ok(result);
+        Ok(result)
     }
 }
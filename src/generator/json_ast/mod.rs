@@ -0,0 +1,363 @@
+use super::*;
+use std::collections::HashMap;
+
+/// Format identifier and version of the exported document. The version is
+/// bumped whenever a field is removed or changes meaning.
+static FORMAT: &str = "xdr-codegen-ast";
+static VERSION: u32 = 1;
+
+/// Length of `<>` declarations, which have no maximum.
+const UNBOUNDED: i32 = 2147483647;
+
+#[derive(Debug, Default)]
+pub struct JsonAstGenerator {}
+
+#[derive(Serialize)]
+struct Document<'a> {
+    format: &'static str,
+    version: u32,
+    namespaces: Vec<NamespaceOut<'a>>,
+}
+
+#[derive(Serialize)]
+struct NamespaceOut<'a> {
+    name: &'a str,
+    typedefs: Vec<TypedefOut<'a>>,
+    structs: Vec<StructOut<'a>>,
+    enums: Vec<EnumOut<'a>>,
+    unions: Vec<UnionOut<'a>>,
+}
+
+#[derive(Serialize)]
+struct TypeRef<'a> {
+    /// Referenced type, an XDR primitive name or a declared type.
+    #[serde(rename = "type")]
+    type_name: &'a str,
+
+    /// One of primitive, typedef, struct, enum or union.
+    kind: &'static str,
+
+    /// Namespace declaring the referenced type, absent for primitives.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<&'a str>,
+
+    /// Array bounds, absent for single values.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    array: Option<ArrayOut>,
+}
+
+#[derive(Serialize)]
+struct ArrayOut {
+    fixed: bool,
+
+    /// Exact length of fixed arrays, maximum length of variable ones and
+    /// null for unbounded variable arrays.
+    length: Option<i32>,
+}
+
+#[derive(Serialize)]
+struct TypedefOut<'a> {
+    name: &'a str,
+    #[serde(flatten)]
+    decl: TypeRef<'a>,
+}
+
+#[derive(Serialize)]
+struct FieldOut<'a> {
+    name: &'a str,
+    #[serde(flatten)]
+    decl: TypeRef<'a>,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    tag: &'a str,
+}
+
+#[derive(Serialize)]
+struct StructOut<'a> {
+    name: &'a str,
+    fields: Vec<FieldOut<'a>>,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    tag: &'a str,
+}
+
+#[derive(Serialize)]
+struct EnumValueOut<'a> {
+    name: &'a str,
+    value: i32,
+}
+
+#[derive(Serialize)]
+struct EnumOut<'a> {
+    name: &'a str,
+    values: Vec<EnumValueOut<'a>>,
+}
+
+#[derive(Serialize)]
+struct ArmOut<'a> {
+    /// Case label as written in the schema.
+    case: &'a str,
+
+    /// Discriminant value selecting this arm.
+    value: i32,
+
+    /// Arm declaration, null for void arms.
+    arm: Option<FieldOut<'a>>,
+}
+
+#[derive(Serialize)]
+struct UnionOut<'a> {
+    name: &'a str,
+    discriminant: FieldOut<'a>,
+    arms: Vec<ArmOut<'a>>,
+}
+
+fn is_primitive(typ: &str) -> bool {
+    matches!(
+        typ,
+        "boolean" | "int" | "unsigned int" | "hyper" | "unsigned hyper" | "float" | "double" | "string" | "opaque"
+    )
+}
+
+/// Declared types by name, with their namespace and kind.
+struct Types<'a> {
+    declared: HashMap<&'a str, (&'a str, &'static str)>,
+    enums: HashMap<&'a str, &'a Enum>,
+}
+
+impl<'a> Types<'a> {
+    fn new(namespaces: &'a [Namespace]) -> Result<Self, &'static str> {
+        let mut declared = HashMap::new();
+        let mut enums = HashMap::new();
+        for ns in namespaces {
+            let defs = ns.typedefs.iter().map(|td| (td.def.name.as_str(), "typedef"));
+            let structs = ns.structs.iter().map(|st| (st.name.as_str(), "struct"));
+            let enum_names = ns.enums.iter().map(|en| (en.name.as_str(), "enum"));
+            let unions = ns.unions.iter().map(|un| (un.name.as_str(), "union"));
+            for (name, kind) in defs.chain(structs).chain(enum_names).chain(unions) {
+                if declared.insert(name, (ns.name.as_str(), kind)).is_some() {
+                    return Err("duplicate type name");
+                }
+            }
+            for en in &ns.enums {
+                enums.insert(en.name.as_str(), en);
+            }
+        }
+        Ok(Types { declared, enums })
+    }
+
+    fn resolve(&self, def: &'a Def) -> Result<TypeRef<'a>, &'static str> {
+        let typ = def.type_name.as_str();
+        let (namespace, kind) = match self.declared.get(typ) {
+            Some(&(ns, kind)) => (Some(ns), kind),
+            None if is_primitive(typ) => (None, "primitive"),
+            None => return Err("reference to an undeclared type"),
+        };
+        let array = match (typ, def.array_size, def.fixed_array) {
+            ("string", size, _) => Some(ArrayOut {
+                fixed: false,
+                length: Some(size).filter(|s| *s != UNBOUNDED && *s != 0),
+            }),
+            (_, 0, _) => None,
+            (_, size, true) => Some(ArrayOut {
+                fixed: true,
+                length: Some(size),
+            }),
+            (_, size, false) => Some(ArrayOut {
+                fixed: false,
+                length: Some(size).filter(|s| *s != UNBOUNDED),
+            }),
+        };
+        Ok(TypeRef {
+            type_name: typ,
+            kind,
+            namespace,
+            array,
+        })
+    }
+
+    fn field(&self, def: &'a Def) -> Result<FieldOut<'a>, &'static str> {
+        Ok(FieldOut {
+            name: &def.name,
+            decl: self.resolve(def)?,
+            tag: &def.tag,
+        })
+    }
+
+    fn union(&self, un: &'a Union) -> Result<UnionOut<'a>, &'static str> {
+        let switch = &un.switch;
+        let en = self.enums.get(switch.enum_type.as_str());
+        let discriminant = FieldOut {
+            name: &switch.enum_name,
+            decl: match en {
+                Some(_) => TypeRef {
+                    type_name: &switch.enum_type,
+                    kind: "enum",
+                    namespace: self.declared.get(switch.enum_type.as_str()).map(|&(ns, _)| ns),
+                    array: None,
+                },
+                None if matches!(switch.enum_type.as_str(), "int" | "unsigned int") => TypeRef {
+                    type_name: &switch.enum_type,
+                    kind: "primitive",
+                    namespace: None,
+                    array: None,
+                },
+                None => return Err("union discriminant must be an enum or integer"),
+            },
+            tag: "",
+        };
+        let arms = switch
+            .cases
+            .iter()
+            .map(|case| {
+                let value = match en {
+                    Some(en) => en
+                        .values
+                        .iter()
+                        .find(|v| v.name == case.value)
+                        .map(|v| v.index)
+                        .ok_or("union case is not a value of its enum")?,
+                    None => case.value.parse().map_err(|_| "union case is not an integer")?,
+                };
+                let arm = match case.ret_type.name.is_empty() {
+                    true => None,
+                    false => Some(self.field(&case.ret_type)?),
+                };
+                Ok(ArmOut {
+                    case: &case.value,
+                    value,
+                    arm,
+                })
+            })
+            .collect::<Result<_, &'static str>>()?;
+        Ok(UnionOut {
+            name: &un.name,
+            discriminant,
+            arms,
+        })
+    }
+}
+
+impl CodeGenerator for JsonAstGenerator {
+    fn code(&self, namespaces: Vec<Namespace>) -> Result<String, &'static str> {
+        let types = Types::new(&namespaces)?;
+        let namespaces = namespaces
+            .iter()
+            .map(|ns| {
+                Ok(NamespaceOut {
+                    name: &ns.name,
+                    typedefs: ns
+                        .typedefs
+                        .iter()
+                        .map(|td| {
+                            Ok(TypedefOut {
+                                name: &td.def.name,
+                                decl: types.resolve(&td.def)?,
+                            })
+                        })
+                        .collect::<Result<_, &'static str>>()?,
+                    structs: ns
+                        .structs
+                        .iter()
+                        .map(|st| {
+                            Ok(StructOut {
+                                name: &st.name,
+                                fields: st.props.iter().map(|prop| types.field(prop)).collect::<Result<_, _>>()?,
+                                tag: &st.tag,
+                            })
+                        })
+                        .collect::<Result<_, &'static str>>()?,
+                    enums: ns
+                        .enums
+                        .iter()
+                        .map(|en| EnumOut {
+                            name: &en.name,
+                            values: en
+                                .values
+                                .iter()
+                                .map(|v| EnumValueOut {
+                                    name: &v.name,
+                                    value: v.index,
+                                })
+                                .collect(),
+                        })
+                        .collect(),
+                    unions: ns.unions.iter().map(|un| types.union(un)).collect::<Result<_, _>>()?,
+                })
+            })
+            .collect::<Result<_, &'static str>>()?;
+        let document = Document {
+            format: FORMAT,
+            version: VERSION,
+            namespaces,
+        };
+        serde_json::to_string_pretty(&document).map_err(|_| "failed to serialize the AST")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn namespaces() -> Vec<Namespace> {
+        build_namespaces(String::from(
+            "namespace test
+{
+    typedef opaque Hash[32];
+
+    enum Kind
+    {
+        A = 0,
+        B = 5
+    };
+
+    struct Item
+    {
+        Hash hashes<>;
+        string name<8>;
+        unsigned hyper amount;
+    };
+
+    union Body switch (Kind Type)
+    {
+        case A:
+            Item item;
+        case B:
+            void;
+    }
+}",
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn document() {
+        let res = JsonAstGenerator {}.code(namespaces());
+        assert!(res.is_ok());
+        let doc: serde_json::Value = serde_json::from_str(&res.unwrap()).unwrap();
+        assert_eq!(doc["format"], "xdr-codegen-ast");
+        assert_eq!(doc["version"], 1);
+        let ns = &doc["namespaces"][0];
+        assert_eq!(
+            ns["typedefs"][0],
+            serde_json::json!({"name": "Hash", "type": "opaque", "kind": "primitive", "array": {"fixed": true, "length": 32}})
+        );
+        let fields = &ns["structs"][0]["fields"];
+        assert_eq!(
+            fields[0],
+            serde_json::json!({"name": "hashes", "type": "Hash", "kind": "typedef", "namespace": "test", "array": {"fixed": false, "length": null}})
+        );
+        assert_eq!(fields[1]["array"]["length"], 8);
+        assert!(fields[2].get("array").is_none());
+        assert_eq!(ns["enums"][0]["values"][1], serde_json::json!({"name": "B", "value": 5}));
+        let body = &ns["unions"][0];
+        assert_eq!(body["discriminant"]["name"], "Type");
+        assert_eq!(body["arms"][0]["arm"]["type"], "Item");
+        assert_eq!(body["arms"][1], serde_json::json!({"case": "B", "value": 5, "arm": null}));
+    }
+
+    #[test]
+    fn undeclared_type() {
+        let mut namespaces = namespaces();
+        namespaces[0].structs[0].props[0].type_name = String::from("Missing");
+        assert_eq!(JsonAstGenerator {}.code(namespaces), Err("reference to an undeclared type"));
+    }
+}
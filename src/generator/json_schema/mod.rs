@@ -0,0 +1,395 @@
+use super::*;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+static DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Length of `<>` declarations, which have no maximum.
+const UNBOUNDED: i32 = 2147483647;
+
+/// Generates one JSON Schema document per namespace describing the JSON
+/// written by the Go generator.
+#[derive(Debug, Default)]
+pub struct JsonSchemaGenerator {}
+
+/// Turns the namespace and name of a declared type into a `$ref` target.
+pub type Reference<'a> = Box<dyn Fn(&str, &str) -> String + 'a>;
+
+/// Builds schemas for the Go JSON shape of XDR declarations.
+pub struct SchemaBuilder<'a> {
+    owners: HashMap<&'a str, &'a str>,
+    enums: HashMap<&'a str, &'a Enum>,
+    reference: Reference<'a>,
+}
+
+impl<'a> SchemaBuilder<'a> {
+    pub fn new(namespaces: &'a [Namespace], reference: Reference<'a>) -> Self {
+        let mut owners = HashMap::new();
+        let mut enums = HashMap::new();
+        for ns in namespaces {
+            for td in &ns.typedefs {
+                owners.insert(td.def.name.as_str(), ns.name.as_str());
+            }
+            for st in &ns.structs {
+                owners.insert(st.name.as_str(), ns.name.as_str());
+            }
+            for en in &ns.enums {
+                owners.insert(en.name.as_str(), ns.name.as_str());
+                enums.insert(en.name.as_str(), en);
+            }
+            for un in &ns.unions {
+                owners.insert(un.name.as_str(), ns.name.as_str());
+            }
+        }
+        SchemaBuilder {
+            owners,
+            enums,
+            reference,
+        }
+    }
+
+    fn type_ref(&self, typ: &str) -> Result<Value, &'static str> {
+        Ok(match typ {
+            "boolean" => json!({"type": "boolean"}),
+            "int" => json!({"type": "integer", "minimum": i32::MIN, "maximum": i32::MAX}),
+            "unsigned int" => json!({"type": "integer", "minimum": 0, "maximum": u32::MAX}),
+            "hyper" => json!({"type": "integer", "minimum": i64::MIN, "maximum": i64::MAX}),
+            "unsigned hyper" => json!({"type": "integer", "minimum": 0, "maximum": u64::MAX}),
+            "float" | "double" => json!({"type": "number"}),
+            typ => match self.owners.get(typ) {
+                Some(ns) => json!({"$ref": (self.reference)(ns, typ)}),
+                None => return Err("reference to an undeclared type"),
+            },
+        })
+    }
+
+    /// Schema of a declaration. Go writes 64 bit struct fields as strings and
+    /// nil slices, including variable length opaque data, as `null`.
+    pub fn decl(&self, def: &Def, field: bool) -> Result<Value, &'static str> {
+        let typ = def.type_name.as_str();
+        let size = def.array_size;
+        let bounded = size != 0 && size != UNBOUNDED;
+        let mut schema = match (typ, size, def.fixed_array) {
+            ("string", _, _) => json!({"type": "string"}),
+            ("hyper", 0, _) if field => json!({"type": "string", "pattern": "^-?[0-9]+$"}),
+            ("unsigned hyper", 0, _) if field => json!({"type": "string", "pattern": "^[0-9]+$"}),
+            (typ, 0, _) => self.type_ref(typ)?,
+            ("opaque", _, true) => json!({"type": "array", "items": {"type": "integer", "minimum": 0, "maximum": 255}}),
+            ("opaque", _, false) => json!({"type": ["string", "null"], "contentEncoding": "base64"}),
+            (typ, _, true) => json!({"type": "array", "items": self.type_ref(typ)?}),
+            (typ, _, false) => json!({"type": ["array", "null"], "items": self.type_ref(typ)?}),
+        };
+        let obj = schema.as_object_mut().unwrap();
+        match (typ, def.fixed_array) {
+            // maxLength counts characters while XDR bounds bytes, so longer
+            // non ASCII strings still pass the schema and fail to encode.
+            ("string", _) if bounded => {
+                obj.insert(String::from("maxLength"), json!(size));
+            }
+            ("opaque", false) if bounded => {
+                obj.insert(String::from("maxLength"), json!((size + 2) / 3 * 4));
+            }
+            (_, true) if size != 0 && typ != "string" => {
+                obj.insert(String::from("minItems"), json!(size));
+                obj.insert(String::from("maxItems"), json!(size));
+            }
+            (_, false) if bounded && typ != "opaque" => {
+                obj.insert(String::from("maxItems"), json!(size));
+            }
+            _ => {}
+        }
+        Ok(schema)
+    }
+
+    pub fn typedef(&self, td: &Typedef) -> Result<Value, &'static str> {
+        self.decl(&td.def, false)
+    }
+
+    pub fn structure(&self, st: &Struct) -> Result<Value, &'static str> {
+        let mut properties = Map::new();
+        for prop in &st.props {
+            properties.insert(to_first_lower(&prop.name), self.decl(prop, true)?);
+        }
+        let required: Vec<String> = st.props.iter().map(|prop| to_first_lower(&prop.name)).collect();
+        Ok(json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        }))
+    }
+
+    pub fn enumeration(&self, en: &Enum) -> Value {
+        let values: Vec<i32> = en.values.iter().map(|v| v.index).collect();
+        json!({"type": "integer", "enum": values})
+    }
+
+    /// Discriminant value of each case of a union.
+    pub fn discriminants<'u>(&self, un: &'u Union) -> Result<Vec<(&'u Case, i32)>, &'static str> {
+        let en = self.enums.get(un.switch.enum_type.as_str());
+        un.switch
+            .cases
+            .iter()
+            .map(|case| {
+                let value = match en {
+                    Some(en) => en
+                        .values
+                        .iter()
+                        .find(|v| v.name == case.value)
+                        .map(|v| v.index)
+                        .ok_or("union case is not a value of its enum")?,
+                    None => case.value.parse().map_err(|_| "union case is not an integer")?,
+                };
+                Ok((case, value))
+            })
+            .collect()
+    }
+
+    /// Schema of one union arm as `{"type": discriminant, "data": arm}`.
+    pub fn union_arm(&self, case: &Case, value: i32) -> Result<Value, &'static str> {
+        let data = match case.ret_type.name.is_empty() {
+            true => json!({"const": ""}),
+            false => self.decl(&case.ret_type, false)?,
+        };
+        Ok(json!({
+            "type": "object",
+            "properties": {
+                "type": {"const": value},
+                "data": data,
+            },
+            "required": ["type", "data"],
+            "additionalProperties": false,
+        }))
+    }
+
+    pub fn union(&self, un: &Union) -> Result<Value, &'static str> {
+        let arms = self
+            .discriminants(un)?
+            .into_iter()
+            .map(|(case, value)| self.union_arm(case, value))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(json!({"oneOf": arms}))
+    }
+
+    /// Schemas of every type declared in a namespace, keyed by type name.
+    pub fn definitions(&self, ns: &Namespace) -> Result<Map<String, Value>, &'static str> {
+        let mut defs = Map::new();
+        for td in &ns.typedefs {
+            defs.insert(td.def.name.clone(), self.typedef(td)?);
+        }
+        for st in &ns.structs {
+            defs.insert(st.name.clone(), self.structure(st)?);
+        }
+        for en in &ns.enums {
+            defs.insert(en.name.clone(), self.enumeration(en));
+        }
+        for un in &ns.unions {
+            defs.insert(un.name.clone(), self.union(un)?);
+        }
+        Ok(defs)
+    }
+}
+
+impl JsonSchemaGenerator {
+    /// File name of the document for a namespace, also used as its `$id`.
+    pub fn file_name(namespace: &str) -> String {
+        format!("{}.schema.json", namespace)
+    }
+
+    /// Renders one document per namespace as (file name, contents) pairs.
+    /// References to types of other namespaces point at their documents.
+    pub fn documents(&self, namespaces: Vec<Namespace>) -> Result<Vec<(String, String)>, &'static str> {
+        namespaces
+            .iter()
+            .map(|ns| {
+                let current = ns.name.clone();
+                let builder = SchemaBuilder::new(
+                    &namespaces,
+                    Box::new(move |owner: &str, typ: &str| match owner == current {
+                        true => format!("#/$defs/{}", typ),
+                        false => format!("{}#/$defs/{}", JsonSchemaGenerator::file_name(owner), typ),
+                    }),
+                );
+                let document = json!({
+                    "$schema": DIALECT,
+                    "$id": JsonSchemaGenerator::file_name(&ns.name),
+                    "title": ns.name,
+                    "$defs": builder.definitions(ns)?,
+                });
+                let contents = serde_json::to_string_pretty(&document).map_err(|_| "failed to serialize the schema")?;
+                Ok((JsonSchemaGenerator::file_name(&ns.name), contents))
+            })
+            .collect()
+    }
+}
+
+impl CodeGenerator for JsonSchemaGenerator {
+    fn code(&self, namespaces: Vec<Namespace>) -> Result<String, &'static str> {
+        let mut documents = self.documents(namespaces)?;
+        match documents.len() {
+            1 => Ok(documents.remove(0).1),
+            0 => Err("no namespace to generate a schema for"),
+            _ => Err("one schema document is written per namespace"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn namespaces() -> Vec<Namespace> {
+        build_namespaces(String::from(
+            "namespace test
+{
+    typedef opaque Hash[32];
+    typedef opaque Data<6>;
+
+    enum Kind
+    {
+        A = 0,
+        B = 5
+    };
+
+    struct Item
+    {
+        Hash hashes<3>;
+        string name<8>;
+        unsigned hyper amount;
+        int values[2];
+    };
+
+    union Body switch (Kind Type)
+    {
+        case A:
+            Item item;
+        case B:
+            void;
+    }
+}",
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn document() {
+        let res = JsonSchemaGenerator {}.code(namespaces());
+        assert!(res.is_ok());
+        let doc: Value = serde_json::from_str(&res.unwrap()).unwrap();
+        assert_eq!(doc["$schema"], DIALECT);
+        assert_eq!(doc["$id"], "test.schema.json");
+        let defs = &doc["$defs"];
+        assert_eq!(defs["Hash"]["minItems"], 32);
+        assert_eq!(defs["Hash"]["maxItems"], 32);
+        assert_eq!(
+            defs["Data"],
+            json!({"type": ["string", "null"], "contentEncoding": "base64", "maxLength": 8})
+        );
+        let props = &defs["Item"]["properties"];
+        assert_eq!(
+            props["hashes"],
+            json!({"type": ["array", "null"], "items": {"$ref": "#/$defs/Hash"}, "maxItems": 3})
+        );
+        assert_eq!(props["name"], json!({"type": "string", "maxLength": 8}));
+        assert_eq!(props["amount"]["type"], "string");
+        assert_eq!(props["values"]["type"], "array");
+        assert_eq!(props["values"]["minItems"], 2);
+        assert_eq!(defs["Item"]["required"], json!(["hashes", "name", "amount", "values"]));
+        assert_eq!(defs["Kind"], json!({"type": "integer", "enum": [0, 5]}));
+        let arms = &defs["Body"]["oneOf"];
+        assert_eq!(arms[0]["properties"]["data"], json!({"$ref": "#/$defs/Item"}));
+        assert_eq!(arms[1]["properties"], json!({"type": {"const": 5}, "data": {"const": ""}}));
+    }
+
+    /// Checks the keywords the generator emits, resolving local `$ref`s.
+    fn valid(defs: &Value, schema: &Value, value: &Value) -> bool {
+        if let Some(target) = schema["$ref"].as_str() {
+            return valid(defs, &defs[target.trim_start_matches("#/$defs/")], value);
+        }
+        if let Some(arms) = schema["oneOf"].as_array() {
+            return arms.iter().filter(|arm| valid(defs, arm, value)).count() == 1;
+        }
+        if !schema["const"].is_null() && schema["const"] != *value {
+            return false;
+        }
+        if let Some(values) = schema["enum"].as_array() {
+            if !values.contains(value) {
+                return false;
+            }
+        }
+        let types: Vec<&str> = match &schema["type"] {
+            Value::String(typ) => vec![typ.as_str()],
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            _ => return true,
+        };
+        let matches = |typ: &str| match (typ, value) {
+            ("null", Value::Null) | ("boolean", Value::Bool(_)) | ("string", Value::String(_)) => true,
+            ("integer", Value::Number(n)) => n.is_i64() || n.is_u64(),
+            ("number", Value::Number(_)) | ("array", Value::Array(_)) | ("object", Value::Object(_)) => true,
+            _ => false,
+        };
+        if !types.into_iter().any(matches) {
+            return false;
+        }
+        let bound = |key: &str| schema[key].as_u64().map(|b| b as usize);
+        match value {
+            Value::String(s) => bound("maxLength").is_none_or(|max| s.chars().count() <= max),
+            Value::Array(items) => {
+                bound("maxItems").is_none_or(|max| items.len() <= max)
+                    && bound("minItems").is_none_or(|min| items.len() >= min)
+                    && items.iter().all(|item| valid(defs, &schema["items"], item))
+            }
+            Value::Object(obj) => {
+                let props = schema["properties"].as_object().unwrap();
+                let required = schema["required"].as_array().unwrap();
+                required.iter().all(|key| obj.contains_key(key.as_str().unwrap()))
+                    && obj.iter().all(|(key, v)| props.get(key).is_some_and(|p| valid(defs, p, v)))
+            }
+            Value::Number(n) => {
+                schema["minimum"].as_f64().is_none_or(|min| n.as_f64().unwrap() >= min)
+                    && schema["maximum"].as_f64().is_none_or(|max| n.as_f64().unwrap() <= max)
+            }
+            _ => true,
+        }
+    }
+
+    #[test]
+    fn go_json() {
+        let doc: Value = serde_json::from_str(&JsonSchemaGenerator {}.code(namespaces()).unwrap()).unwrap();
+        let defs = &doc["$defs"];
+        // encoding/json output for zero values, where nil slices are null.
+        let item = json!({"hashes": null, "name": "", "amount": "0", "values": [0, 0]});
+        assert!(valid(defs, &defs["Item"], &item));
+        assert!(valid(defs, &defs["Data"], &Value::Null));
+        assert!(valid(defs, &defs["Body"], &json!({"type": 0, "data": item})));
+        assert!(valid(defs, &defs["Body"], &json!({"type": 5, "data": ""})));
+        let full = json!({"hashes": [vec![7; 32]], "name": "abc", "amount": "18446744073709551615", "values": [1, -1]});
+        assert!(valid(defs, &defs["Item"], &full));
+        assert!(valid(defs, &defs["Data"], &json!("AAECAwQF")));
+        assert!(!valid(defs, &defs["Item"], &json!({"hashes": null, "name": "", "amount": "0", "values": null})));
+        assert!(!valid(defs, &defs["Item"], &json!({"hashes": [], "name": "too long!", "amount": "0", "values": [0, 0]})));
+        assert!(!valid(defs, &defs["Body"], &json!({"type": 5, "data": null})));
+    }
+
+    #[test]
+    fn cross_namespace_references() {
+        let mut namespaces = namespaces();
+        namespaces.push(Namespace {
+            name: String::from("other"),
+            structs: vec![Struct {
+                name: String::from("Wrapper"),
+                props: vec![Def {
+                    name: String::from("Body"),
+                    type_name: String::from("Body"),
+                    ..Default::default()
+                }],
+                tag: String::new(),
+                comment: String::new(),
+            }],
+            ..Default::default()
+        });
+        let documents = JsonSchemaGenerator {}.documents(namespaces).unwrap();
+        assert_eq!(documents[1].0, "other.schema.json");
+        assert!(documents[1].1.contains("\"$ref\": \"test.schema.json#/$defs/Body\""));
+    }
+}
@@ -2,14 +2,60 @@ use super::ast::*;
 use std::collections::HashMap;
 
 pub mod commonjs;
+pub mod docs;
 pub mod go;
 pub mod js;
+pub mod json_ast;
+pub mod json_schema;
+pub mod openapi;
+pub mod proto;
 pub mod rust;
 
 pub trait CodeGenerator {
     fn code(&self, namespace: Vec<Namespace>) -> Result<String, &'static str>;
 }
 
+pub fn to_first_lower(value: &str) -> String {
+    let mut c = value.chars();
+    match c.next() {
+        None => String::new(),
+        Some(f) => f.to_lowercase().collect::<String>() + c.as_str(),
+    }
+}
+
+/// Formats a schema comment as one line comment per line, each starting with
+/// `prefix` such as `  // ` or `/// `.
+pub fn doc_comment(comment: &str, prefix: &str) -> String {
+    comment
+        .lines()
+        .map(|line| format!("{}{}", prefix, line).trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Builds a JSDoc block from the comment of a type and the comments of its
+/// members, documented as `@property` tags named by `key`. Empty when nothing
+/// is documented.
+pub fn js_doc(comment: &str, members: &[serde_json::Value], key: &str) -> String {
+    let escape = |text: &str| text.replace("*/", "*\\/");
+    let mut lines: Vec<String> = comment.lines().map(escape).collect();
+    for member in members {
+        let docs: Vec<&str> = [&member["comment"], &member["ret_type"]["comment"]]
+            .iter()
+            .filter_map(|doc| doc.as_str())
+            .filter(|doc| !doc.is_empty())
+            .collect();
+        if !docs.is_empty() {
+            let text = docs.join(" ").split_whitespace().collect::<Vec<_>>().join(" ");
+            lines.push(format!("@property {} {}", member[key].as_str().unwrap_or_default(), escape(&text)));
+        }
+    }
+    match lines.is_empty() {
+        true => String::new(),
+        false => format!("/**\n{}\n */\n", doc_comment(&lines.join("\n"), " * ")),
+    }
+}
+
 pub fn apply_type_map(mut namespaces: Vec<Namespace>, type_map: &HashMap<&str, &str>) -> Result<Vec<Namespace>, &'static str> {
     for namespace in &mut namespaces {
         for typedef in &mut namespace.typedefs {
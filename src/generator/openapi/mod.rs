@@ -0,0 +1,197 @@
+use super::json_schema::SchemaBuilder;
+use super::*;
+use serde_json::{json, Map, Value};
+
+static VERSION: &str = "3.1.0";
+
+/// Generates an OpenAPI document whose `components.schemas` describe the JSON
+/// written by the Go generator, with unions in the `--go-union-names` shape.
+#[derive(Debug, Default)]
+pub struct OpenApiGenerator {}
+
+fn reference(typ: &str) -> String {
+    format!("#/components/schemas/{}", typ)
+}
+
+/// Component name of the arm of a union selected by `case`.
+fn arm_name(un: &Union, case: &Case) -> String {
+    format!("{}_{}", un.name, case.value)
+}
+
+/// Property holding the case name, whatever the switch is called.
+static DISCRIMINATOR: &str = "type";
+
+/// Schema of one arm as `{"type": "<case>", "<arm>": ...}`.
+fn arm(builder: &SchemaBuilder, case: &Case) -> Result<Value, &'static str> {
+    let property = String::from(DISCRIMINATOR);
+    let mut properties = Map::new();
+    properties.insert(property.clone(), json!({"type": "string", "const": case.value}));
+    let mut required = vec![property];
+    if !case.ret_type.name.is_empty() {
+        let name = to_first_lower(&case.ret_type.name);
+        properties.insert(name.clone(), builder.decl(&case.ret_type, false)?);
+        required.push(name);
+    }
+    Ok(json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    }))
+}
+
+/// Union of its arm components, selected by the name of the case.
+fn union(un: &Union) -> Value {
+    let arms: Vec<Value> = un
+        .switch
+        .cases
+        .iter()
+        .map(|case| json!({"$ref": reference(&arm_name(un, case))}))
+        .collect();
+    let mapping: Map<String, Value> = un
+        .switch
+        .cases
+        .iter()
+        .map(|case| (case.value.clone(), json!(reference(&arm_name(un, case)))))
+        .collect();
+    json!({
+        "oneOf": arms,
+        "discriminator": {
+            "propertyName": DISCRIMINATOR,
+            "mapping": mapping,
+        },
+    })
+}
+
+impl CodeGenerator for OpenApiGenerator {
+    fn code(&self, namespaces: Vec<Namespace>) -> Result<String, &'static str> {
+        let builder = SchemaBuilder::new(&namespaces, Box::new(|_: &str, typ: &str| reference(typ)));
+        let mut schemas = Map::new();
+        for ns in &namespaces {
+            for (name, schema) in builder.definitions(ns)? {
+                if schemas.insert(name, schema).is_some() {
+                    return Err("duplicate type name");
+                }
+            }
+            // Replace the default union shape with the discriminated one.
+            for un in &ns.unions {
+                for case in &un.switch.cases {
+                    if schemas.insert(arm_name(un, case), arm(&builder, case)?).is_some() {
+                        return Err("union arm component has the name of another type");
+                    }
+                }
+                schemas.insert(un.name.clone(), union(un));
+            }
+        }
+        let title: Vec<&str> = namespaces.iter().map(|ns| ns.name.as_str()).collect();
+        let document = json!({
+            "openapi": VERSION,
+            "info": {
+                "title": title.join(", "),
+                "version": "1.0.0",
+            },
+            "components": {
+                "schemas": schemas,
+            },
+        });
+        serde_json::to_string_pretty(&document).map_err(|_| "failed to serialize the document")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn namespaces() -> Vec<Namespace> {
+        build_namespaces(String::from(
+            "namespace test
+{
+    enum Kind
+    {
+        CALL = 0,
+        NONE = 5
+    };
+
+    struct Call
+    {
+        string function<32>;
+        hyper nonce;
+    };
+
+    union Action switch (Kind Type)
+    {
+        case CALL:
+            Call call;
+        case NONE:
+            void;
+    }
+}",
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn components() {
+        let res = OpenApiGenerator {}.code(namespaces());
+        assert!(res.is_ok());
+        let doc: Value = serde_json::from_str(&res.unwrap()).unwrap();
+        assert_eq!(doc["openapi"], "3.1.0");
+        let schemas = &doc["components"]["schemas"];
+        assert_eq!(schemas["Call"]["properties"]["nonce"]["type"], "string");
+        assert_eq!(schemas["Kind"], json!({"type": "integer", "enum": [0, 5]}));
+        assert_eq!(
+            schemas["Action"]["discriminator"],
+            json!({
+                "propertyName": "type",
+                "mapping": {
+                    "CALL": "#/components/schemas/Action_CALL",
+                    "NONE": "#/components/schemas/Action_NONE",
+                },
+            })
+        );
+        assert_eq!(
+            schemas["Action"]["oneOf"][0],
+            json!({"$ref": "#/components/schemas/Action_CALL"})
+        );
+        assert_eq!(
+            schemas["Action_CALL"]["properties"],
+            json!({
+                "type": {"type": "string", "const": "CALL"},
+                "call": {"$ref": "#/components/schemas/Call"},
+            })
+        );
+        assert_eq!(schemas["Action_NONE"]["required"], json!(["type"]));
+    }
+
+    #[test]
+    fn discriminator_property() {
+        let mut namespaces = namespaces();
+        namespaces[0].unions[0].switch.enum_name = String::from("kind");
+        let doc: Value = serde_json::from_str(&OpenApiGenerator {}.code(namespaces).unwrap()).unwrap();
+        let schemas = &doc["components"]["schemas"];
+        assert_eq!(schemas["Action"]["discriminator"]["propertyName"], "type");
+        assert_eq!(schemas["Action_CALL"]["required"], json!(["type", "call"]));
+    }
+
+    #[test]
+    fn arm_clash() {
+        let mut namespaces = namespaces();
+        let mut clash = namespaces[0].structs[0].clone();
+        clash.name = String::from("Action_CALL");
+        namespaces[0].structs.push(clash);
+        assert_eq!(
+            OpenApiGenerator {}.code(namespaces),
+            Err("union arm component has the name of another type")
+        );
+    }
+
+    #[test]
+    fn bad_case() {
+        let mut namespaces = namespaces();
+        namespaces[0].unions[0].switch.cases[1].value = String::from("MISSING");
+        assert_eq!(
+            OpenApiGenerator {}.code(namespaces),
+            Err("union case is not a value of its enum")
+        );
+    }
+}
@@ -0,0 +1,532 @@
+use super::*;
+use handlebars::Handlebars;
+use std::collections::HashMap;
+
+static FILE_T: &str = r#"// Code generated by xdr-codegen. DO NOT EDIT.
+// Namespace {{package}}
+syntax = "proto3";
+
+package {{package}};
+{{#each imports as |import|}}
+
+import "{{import}}";
+{{/each}}
+{{#each enums as |enum|}}
+
+enum {{enum.name}} {
+{{#each enum.values as |val|}}
+  {{val.name}} = {{val.number}};{{#if val.comment}} // {{val.comment}}{{/if}}
+{{/each}}
+}
+{{/each}}
+{{#each messages as |msg|}}
+
+{{#each msg.comments as |comment|}}
+// {{comment}}
+{{/each}}
+message {{msg.name}} {
+{{#each msg.fields as |field|}}
+  {{#if field.repeated}}repeated {{/if}}{{field.type_name}} {{field.name}} = {{field.number}};{{#if field.comment}} // {{field.comment}}{{/if}}
+{{/each}}
+{{#if msg.oneof}}
+
+  oneof {{msg.oneof}} {
+{{#each msg.arms as |field|}}
+    {{#if field.repeated}}repeated {{/if}}{{field.type_name}} {{field.name}} = {{field.number}};{{#if field.comment}} // {{field.comment}}{{/if}}
+{{/each}}
+  }
+{{/if}}
+}
+{{/each}}
+"#;
+
+/// Length of `<>` declarations, which have no maximum.
+const UNBOUNDED: i32 = 2147483647;
+
+/// Largest protobuf field number and the range reserved by the implementation.
+const MAX_FIELD: i64 = 536_870_911;
+const RESERVED_FIELDS: std::ops::RangeInclusive<i64> = 19000..=19999;
+
+/// Converts the XDR schema to proto3, one file per namespace.
+///
+/// Struct fields are numbered from 1 in declaration order, which is also
+/// their XDR wire order. Unions are a message with the discriminant as field
+/// 1 and a `oneof` of the non void arms, each numbered by its discriminant
+/// value plus 2 so that adding or reordering cases keeps existing numbers.
+/// Size bounds, typedef names and other details protobuf cannot express are
+/// kept as comments.
+#[derive(Debug, Default)]
+pub struct ProtoGenerator {}
+
+#[derive(Serialize)]
+struct FileOut {
+    package: String,
+    imports: Vec<String>,
+    enums: Vec<EnumOut>,
+    messages: Vec<MessageOut>,
+}
+
+#[derive(Serialize)]
+struct EnumOut {
+    name: String,
+    values: Vec<EnumValueOut>,
+}
+
+#[derive(Serialize)]
+struct EnumValueOut {
+    name: String,
+    number: i32,
+    comment: String,
+}
+
+#[derive(Serialize, Default)]
+struct MessageOut {
+    name: String,
+    comments: Vec<String>,
+    fields: Vec<FieldOut>,
+    oneof: String,
+    arms: Vec<FieldOut>,
+}
+
+#[derive(Serialize)]
+struct FieldOut {
+    repeated: bool,
+    type_name: String,
+    name: String,
+    number: i64,
+    comment: String,
+}
+
+/// Converts `camelCase`, `CamelCase` and `ID` style names to `snake_case`.
+pub fn to_snake_case(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    let mut out = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 && !out.ends_with('_') {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_lower) {
+                out.push('_');
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+fn to_first_upper(value: &str) -> String {
+    let mut c = value.chars();
+    match c.next() {
+        None => String::new(),
+        Some(f) => f.to_uppercase().collect::<String>() + c.as_str(),
+    }
+}
+
+fn scalar(typ: &str) -> Option<&'static str> {
+    match typ {
+        "boolean" => Some("bool"),
+        "int" => Some("int32"),
+        "unsigned int" => Some("uint32"),
+        "hyper" => Some("int64"),
+        "unsigned hyper" => Some("uint64"),
+        "float" => Some("float"),
+        "double" => Some("double"),
+        "string" => Some("string"),
+        "opaque" => Some("bytes"),
+        _ => None,
+    }
+}
+
+/// Describes the bounds of a declaration that protobuf drops.
+fn bounds(def: &Def) -> Option<String> {
+    let size = def.array_size;
+    match (def.type_name.as_str(), def.fixed_array) {
+        (_, _) if size == 0 || size == UNBOUNDED => None,
+        ("string", _) => Some(format!("max length {}", size)),
+        ("opaque", true) => Some(format!("fixed size {} bytes", size)),
+        ("opaque", false) => Some(format!("max size {} bytes", size)),
+        (_, true) => Some(format!("fixed length {}", size)),
+        (_, false) => Some(format!("max length {}", size)),
+    }
+}
+
+/// Whether a declaration is a list in protobuf. Strings and opaque data are
+/// single values whatever their bounds.
+fn is_list(def: &Def) -> bool {
+    def.array_size != 0 && def.type_name != "string" && def.type_name != "opaque"
+}
+
+struct Types<'a> {
+    owners: HashMap<&'a str, &'a str>,
+    typedefs: HashMap<&'a str, &'a Def>,
+    enums: HashMap<&'a str, &'a Enum>,
+}
+
+/// Protobuf type of a declaration with the comments describing what it lost.
+struct Resolved {
+    type_name: String,
+    repeated: bool,
+    comments: Vec<String>,
+}
+
+impl<'a> Types<'a> {
+    fn new(namespaces: &'a [Namespace]) -> Self {
+        let mut owners = HashMap::new();
+        let mut typedefs = HashMap::new();
+        let mut enums = HashMap::new();
+        for ns in namespaces {
+            for td in &ns.typedefs {
+                owners.insert(td.def.name.as_str(), ns.name.as_str());
+                typedefs.insert(td.def.name.as_str(), &td.def);
+            }
+            for st in &ns.structs {
+                owners.insert(st.name.as_str(), ns.name.as_str());
+            }
+            for en in &ns.enums {
+                owners.insert(en.name.as_str(), ns.name.as_str());
+                enums.insert(en.name.as_str(), en);
+            }
+            for un in &ns.unions {
+                owners.insert(un.name.as_str(), ns.name.as_str());
+            }
+        }
+        Types { owners, typedefs, enums }
+    }
+
+    /// Name of a declared type as seen from `package`.
+    fn qualified(&self, typ: &str, package: &str) -> Result<String, &'static str> {
+        match self.owners.get(typ) {
+            Some(&owner) if owner == package => Ok(typ.to_string()),
+            Some(&owner) => Ok(format!("{}.{}", owner, typ)),
+            None => Err("reference to an undeclared type"),
+        }
+    }
+
+    /// Resolves a declaration. Typedefs of single values are inlined since
+    /// protobuf has no aliases, typedefs of lists are wrapper messages.
+    fn resolve(&self, def: &Def, package: &str) -> Result<Resolved, &'static str> {
+        let mut comments: Vec<String> = bounds(def).into_iter().collect();
+        let mut typ = def.type_name.as_str();
+        while let Some(td) = self.typedefs.get(typ).filter(|td| !is_list(td)) {
+            comments.push(match bounds(td) {
+                Some(bounds) => format!("{}: {}", td.name, bounds),
+                None => td.name.clone(),
+            });
+            typ = td.type_name.as_str();
+        }
+        let type_name = match scalar(typ) {
+            Some(scalar) => scalar.to_string(),
+            None => self.qualified(typ, package)?,
+        };
+        Ok(Resolved {
+            type_name,
+            repeated: is_list(def),
+            comments,
+        })
+    }
+
+    fn field(&self, def: &Def, number: i64, package: &str) -> Result<FieldOut, &'static str> {
+        let resolved = self.resolve(def, package)?;
+        Ok(FieldOut {
+            repeated: resolved.repeated,
+            type_name: resolved.type_name,
+            name: to_snake_case(&def.name),
+            number,
+            comment: resolved.comments.join(", "),
+        })
+    }
+
+    fn typedef(&self, def: &Def, package: &str) -> Result<MessageOut, &'static str> {
+        Ok(MessageOut {
+            name: def.name.clone(),
+            comments: vec![format!("Wraps typedef {} since protobuf has no aliases.", def.name)],
+            fields: vec![self.field(
+                &Def {
+                    name: String::from("values"),
+                    ..def.clone()
+                },
+                1,
+                package,
+            )?],
+            ..Default::default()
+        })
+    }
+
+    fn structure(&self, st: &Struct, package: &str) -> Result<MessageOut, &'static str> {
+        Ok(MessageOut {
+            name: st.name.clone(),
+            fields: st
+                .props
+                .iter()
+                .enumerate()
+                .map(|(i, prop)| self.field(prop, i as i64 + 1, package))
+                .collect::<Result<_, _>>()?,
+            ..Default::default()
+        })
+    }
+
+    fn enumeration(&self, en: &Enum) -> EnumOut {
+        let prefix = to_snake_case(&en.name).to_uppercase();
+        let mut values: Vec<EnumValueOut> = en
+            .values
+            .iter()
+            .map(|v| EnumValueOut {
+                name: format!("{}_{}", prefix, to_snake_case(&v.name).to_uppercase()),
+                number: v.index,
+                comment: String::new(),
+            })
+            .collect();
+        if !values.iter().any(|v| v.number == 0) {
+            values.insert(
+                0,
+                EnumValueOut {
+                    name: format!("{}_UNSPECIFIED", prefix),
+                    number: 0,
+                    comment: String::from("not in XDR, proto3 enums must start with zero"),
+                },
+            );
+        }
+        values.sort_by_key(|v| v.number != 0);
+        EnumOut {
+            name: en.name.clone(),
+            values,
+        }
+    }
+
+    /// The union message followed by wrappers of list arms, which `oneof`
+    /// cannot hold directly.
+    fn union(&self, un: &Union, package: &str) -> Result<Vec<MessageOut>, &'static str> {
+        let switch = &un.switch;
+        let en = self.enums.get(switch.enum_type.as_str());
+        let discriminant = match en {
+            Some(_) => self.qualified(&switch.enum_type, package)?,
+            None => match scalar(&switch.enum_type) {
+                Some(typ @ "int32") | Some(typ @ "uint32") => typ.to_string(),
+                _ => return Err("union discriminant must be an enum or integer"),
+            },
+        };
+        let mut message = MessageOut {
+            name: un.name.clone(),
+            fields: vec![FieldOut {
+                repeated: false,
+                type_name: discriminant,
+                name: to_snake_case(&switch.enum_name),
+                number: 1,
+                comment: String::new(),
+            }],
+            oneof: String::from("value"),
+            ..Default::default()
+        };
+        let mut wrappers = Vec::new();
+        let mut voids = Vec::new();
+        for case in &switch.cases {
+            let value = match en {
+                Some(en) => en
+                    .values
+                    .iter()
+                    .find(|v| v.name == case.value)
+                    .map(|v| i64::from(v.index))
+                    .ok_or("union case is not a value of its enum")?,
+                None => case.value.parse().map_err(|_| "union case is not an integer")?,
+            };
+            if case.ret_type.name.is_empty() {
+                voids.push(case.value.as_str());
+                continue;
+            }
+            let number = value + 2;
+            if number > MAX_FIELD || RESERVED_FIELDS.contains(&number) {
+                return Err("union discriminant out of the protobuf field number range");
+            }
+            let mut arm = self.field(&case.ret_type, number, package)?;
+            if arm.repeated {
+                let name = format!("{}{}List", un.name, to_first_upper(&case.ret_type.name));
+                wrappers.push(MessageOut {
+                    name: name.clone(),
+                    comments: vec![format!(
+                        "Wraps the {} arm of {} since oneof cannot hold lists.",
+                        case.value, un.name
+                    )],
+                    fields: vec![FieldOut {
+                        name: String::from("values"),
+                        number: 1,
+                        ..arm
+                    }],
+                    ..Default::default()
+                });
+                arm = FieldOut {
+                    repeated: false,
+                    type_name: name,
+                    name: to_snake_case(&case.ret_type.name),
+                    number,
+                    comment: String::new(),
+                };
+            }
+            message.arms.push(arm);
+        }
+        if !voids.is_empty() {
+            message.comments.push(format!(
+                "Void arms {} are selected by {} alone.",
+                voids.join(", "),
+                to_snake_case(&switch.enum_name)
+            ));
+        }
+        let mut messages = vec![message];
+        messages.append(&mut wrappers);
+        Ok(messages)
+    }
+
+    fn file(&self, ns: &Namespace) -> Result<FileOut, &'static str> {
+        let package = ns.name.as_str();
+        let mut messages = Vec::new();
+        for td in ns.typedefs.iter().filter(|td| is_list(&td.def)) {
+            messages.push(self.typedef(&td.def, package)?);
+        }
+        for st in &ns.structs {
+            messages.push(self.structure(st, package)?);
+        }
+        for un in &ns.unions {
+            messages.append(&mut self.union(un, package)?);
+        }
+        let prefix = |name: &str| name.split('.').next().unwrap().to_string();
+        let mut imports: Vec<String> = messages
+            .iter()
+            .flat_map(|msg| msg.fields.iter().chain(msg.arms.iter()))
+            .filter(|field| field.type_name.contains('.'))
+            .map(|field| ProtoGenerator::file_name(&prefix(&field.type_name)))
+            .collect();
+        imports.sort();
+        imports.dedup();
+        Ok(FileOut {
+            package: package.to_string(),
+            imports,
+            enums: ns.enums.iter().map(|en| self.enumeration(en)).collect(),
+            messages,
+        })
+    }
+}
+
+impl ProtoGenerator {
+    /// File name of the `.proto` file of a namespace, also used to import it.
+    pub fn file_name(namespace: &str) -> String {
+        format!("{}.proto", namespace)
+    }
+
+    /// Renders one file per namespace as (file name, contents) pairs.
+    pub fn documents(&self, namespaces: Vec<Namespace>) -> Result<Vec<(String, String)>, &'static str> {
+        let types = Types::new(&namespaces);
+        let mut reg = Handlebars::new();
+        reg.register_escape_fn(handlebars::no_escape);
+        namespaces
+            .iter()
+            .map(|ns| {
+                let file = types.file(ns)?;
+                let code = reg
+                    .render_template(FILE_T, &file)
+                    .map_err(|_| "failed to render the proto file")?;
+                Ok((ProtoGenerator::file_name(&ns.name), code))
+            })
+            .collect()
+    }
+}
+
+impl CodeGenerator for ProtoGenerator {
+    fn code(&self, namespaces: Vec<Namespace>) -> Result<String, &'static str> {
+        let mut documents = self.documents(namespaces)?;
+        match documents.len() {
+            1 => Ok(documents.remove(0).1),
+            0 => Err("no namespace to generate a proto file for"),
+            _ => Err("one proto file is written per namespace"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn namespaces() -> Vec<Namespace> {
+        build_namespaces(String::from(
+            "namespace test
+{
+    typedef opaque Hash[32];
+    typedef int Values<4>;
+
+    enum Kind
+    {
+        NONE = 1,
+        CALL = 2,
+        LIST = 3
+    };
+
+    struct Call
+    {
+        string function<256>;
+        Hash hashes<>;
+        unsigned hyper channelId;
+        Values values;
+    };
+
+    union Action switch (Kind Type)
+    {
+        case NONE:
+            void;
+        case CALL:
+            Call call;
+        case LIST:
+            boolean flags<>;
+    }
+}",
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn snake_case() {
+        assert_eq!(to_snake_case("channelId"), "channel_id");
+        assert_eq!(to_snake_case("ActionCategoryType"), "action_category_type");
+        assert_eq!(to_snake_case("ID"), "id");
+        assert_eq!(to_snake_case("Type_Int"), "type_int");
+        assert_eq!(to_snake_case("HTTPServer"), "http_server");
+    }
+
+    #[test]
+    fn proto() {
+        let res = ProtoGenerator {}.code(namespaces());
+        assert!(res.is_ok());
+        let code = res.unwrap();
+        assert!(code.contains("syntax = \"proto3\";\n\npackage test;\n"));
+        assert!(code.contains(
+            "enum Kind {\n  KIND_UNSPECIFIED = 0; // not in XDR, proto3 enums must start with zero\n  KIND_NONE = 1;\n"
+        ));
+        assert!(code.contains("message Values {\n  repeated int32 values = 1; // max length 4\n}"));
+        assert!(code.contains("  string function = 1; // max length 256\n"));
+        assert!(code.contains("  repeated bytes hashes = 2; // Hash: fixed size 32 bytes\n"));
+        assert!(code.contains("  uint64 channel_id = 3;\n  Values values = 4;\n}"));
+        assert!(code.contains("// Void arms NONE are selected by type alone.\nmessage Action {\n  Kind type = 1;\n"));
+        assert!(code.contains("  oneof value {\n    Call call = 4;\n    ActionFlagsList flags = 5;\n  }\n"));
+        assert!(code.contains("message ActionFlagsList {\n  repeated bool values = 1;\n}"));
+    }
+
+    #[test]
+    fn cross_namespace_import() {
+        let mut namespaces = namespaces();
+        namespaces.push(Namespace {
+            name: String::from("other"),
+            structs: vec![Struct {
+                name: String::from("Wrapper"),
+                props: vec![Def {
+                    name: String::from("action"),
+                    type_name: String::from("Action"),
+                    ..Default::default()
+                }],
+                tag: String::new(),
+                comment: String::new(),
+            }],
+            ..Default::default()
+        });
+        let documents = ProtoGenerator {}.documents(namespaces).unwrap();
+        assert_eq!(documents[1].0, "other.proto");
+        assert!(documents[1].1.contains("import \"test.proto\";"));
+        assert!(documents[1].1.contains("  test.Action action = 1;\n"));
+    }
+}
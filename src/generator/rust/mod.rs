@@ -22,8 +22,541 @@ use xdr_rs_serialize::ser::{
 use std::io::Write;
 
 extern crate json;
+"#;
+
+static NO_STD_HEADER: &str = r#"
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+extern crate alloc;
+#[allow(unused_imports)]
+use alloc::string::{String, ToString};
+#[allow(unused_imports)]
+use alloc::vec::Vec;
+"#;
+
+static RUNTIME_T: &str = r#"
+// Start XDR runtime
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    ByteBadFormat,
+    BoolBadFormat,
+    StringBadFormat,
+    FixedArrayWrongSize,
+    VarArrayWrongSize,
+    BadArraySize,
+    InvalidEnumValue,
+}
+
+impl Error {
+    pub fn fixed_array_wrong_size() -> Self {
+        Error::FixedArrayWrongSize
+    }
+
+    pub fn var_array_wrong_size() -> Self {
+        Error::VarArrayWrongSize
+    }
+
+    pub fn invalid_enum_value() -> Self {
+        Error::InvalidEnumValue
+    }
+
+    pub fn string_bad_format() -> Self {
+        Error::StringBadFormat
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+pub trait XDROut {
+    fn write_xdr(&self, out: &mut Vec<u8>) -> Result<u64, Error>;
+}
+
+pub trait XDRIn: Sized {
+    fn read_xdr(buffer: &[u8]) -> Result<(Self, u64), Error>;
+}
+
+fn take(buffer: &[u8], len: usize) -> Result<&[u8], Error> {
+    buffer.get(..len).ok_or(Error::ByteBadFormat)
+}
+
+fn pad(written: u64, out: &mut Vec<u8>) -> u64 {
+    let padding = (4 - written % 4) % 4;
+    for _ in 0..padding {
+        out.push(0);
+    }
+    padding
+}
+
+macro_rules! xdr_number {
+    ($t:ty, $len:expr) => {
+        impl XDROut for $t {
+            fn write_xdr(&self, out: &mut Vec<u8>) -> Result<u64, Error> {
+                out.extend_from_slice(&self.to_be_bytes());
+                Ok($len)
+            }
+        }
+
+        impl XDRIn for $t {
+            fn read_xdr(buffer: &[u8]) -> Result<(Self, u64), Error> {
+                let mut bytes = [0u8; $len];
+                bytes.copy_from_slice(take(buffer, $len)?);
+                Ok((<$t>::from_be_bytes(bytes), $len))
+            }
+        }
+    };
+}
+
+xdr_number!(i32, 4);
+xdr_number!(u32, 4);
+xdr_number!(i64, 8);
+xdr_number!(u64, 8);
+xdr_number!(f32, 4);
+xdr_number!(f64, 8);
+
+impl XDROut for bool {
+    fn write_xdr(&self, out: &mut Vec<u8>) -> Result<u64, Error> {
+        (*self as u32).write_xdr(out)
+    }
+}
+
+impl XDRIn for bool {
+    fn read_xdr(buffer: &[u8]) -> Result<(Self, u64), Error> {
+        match u32::read_xdr(buffer)? {
+            (0, read) => Ok((false, read)),
+            (1, read) => Ok((true, read)),
+            _ => Err(Error::BoolBadFormat),
+        }
+    }
+}
+
+impl XDROut for () {
+    fn write_xdr(&self, _out: &mut Vec<u8>) -> Result<u64, Error> {
+        Ok(0)
+    }
+}
+
+impl XDRIn for () {
+    fn read_xdr(_buffer: &[u8]) -> Result<(Self, u64), Error> {
+        Ok(((), 0))
+    }
+}
+
+impl XDROut for String {
+    fn write_xdr(&self, out: &mut Vec<u8>) -> Result<u64, Error> {
+        write_var_opaque(self.as_bytes(), u32::MAX, out)
+    }
+}
+
+impl XDRIn for String {
+    fn read_xdr(buffer: &[u8]) -> Result<(Self, u64), Error> {
+        read_var_string(u32::MAX, buffer)
+    }
+}
+
+pub fn write_fixed_array<T: XDROut>(val: &[T], size: u32, out: &mut Vec<u8>) -> Result<u64, Error> {
+    if val.len() as u32 != size {
+        return Err(Error::FixedArrayWrongSize);
+    }
+    let mut written: u64 = 0;
+    for item in val {
+        written += item.write_xdr(out)?;
+    }
+    Ok(written)
+}
+
+pub fn write_var_array<T: XDROut>(val: &[T], size: u32, out: &mut Vec<u8>) -> Result<u64, Error> {
+    if val.len() as u32 > size {
+        return Err(Error::VarArrayWrongSize);
+    }
+    let written = (val.len() as u32).write_xdr(out)?;
+    Ok(written + write_fixed_array(val, val.len() as u32, out)?)
+}
+
+pub fn write_fixed_opaque(val: &[u8], size: u32, out: &mut Vec<u8>) -> Result<u64, Error> {
+    if val.len() as u32 != size {
+        return Err(Error::FixedArrayWrongSize);
+    }
+    out.extend_from_slice(val);
+    let written = val.len() as u64;
+    Ok(written + pad(written, out))
+}
+
+pub fn write_var_opaque(val: &[u8], size: u32, out: &mut Vec<u8>) -> Result<u64, Error> {
+    if val.len() as u32 > size {
+        return Err(Error::BadArraySize);
+    }
+    let written = (val.len() as u32).write_xdr(out)?;
+    Ok(written + write_fixed_opaque(val, val.len() as u32, out)?)
+}
+
+pub fn write_var_string(val: &str, size: u32, out: &mut Vec<u8>) -> Result<u64, Error> {
+    if val.len() as u32 > size {
+        return Err(Error::VarArrayWrongSize);
+    }
+    write_var_opaque(val.as_bytes(), size, out)
+}
+
+pub fn read_fixed_array<T: XDRIn>(size: u32, buffer: &[u8]) -> Result<(Vec<T>, u64), Error> {
+    let mut read: u64 = 0;
+    let mut result = Vec::new();
+    for _ in 0..size {
+        let item = T::read_xdr(buffer.get(read as usize..).ok_or(Error::ByteBadFormat)?)?;
+        read += item.1;
+        result.push(item.0);
+    }
+    Ok((result, read))
+}
+
+pub fn read_var_array<T: XDRIn>(size: u32, buffer: &[u8]) -> Result<(Vec<T>, u64), Error> {
+    let length = u32::read_xdr(buffer)?.0;
+    if length > size {
+        return Err(Error::VarArrayWrongSize);
+    }
+    let result = read_fixed_array(length, &buffer[4..])?;
+    Ok((result.0, result.1 + 4))
+}
+
+pub fn read_fixed_opaque(size: u32, buffer: &[u8]) -> Result<(Vec<u8>, u64), Error> {
+    let padded_size = (4 - size % 4) % 4 + size;
+    let bytes = take(buffer, padded_size as usize)?;
+    Ok((bytes[..size as usize].to_vec(), padded_size as u64))
+}
+
+pub fn read_var_opaque(size: u32, buffer: &[u8]) -> Result<(Vec<u8>, u64), Error> {
+    let length = u32::read_xdr(buffer)?.0;
+    if length > size {
+        return Err(Error::BadArraySize);
+    }
+    let result = read_fixed_opaque(length, &buffer[4..])?;
+    Ok((result.0, result.1 + 4))
+}
+
+pub fn read_var_string(size: u32, buffer: &[u8]) -> Result<(String, u64), Error> {
+    if u32::read_xdr(buffer)?.0 > size {
+        return Err(Error::VarArrayWrongSize);
+    }
+    let (bytes, read) = read_var_opaque(size, buffer)?;
+    match String::from_utf8(bytes) {
+        Ok(val) => Ok((val, read)),
+        Err(_) => Err(Error::StringBadFormat),
+    }
+}
+
+// End XDR runtime
+"#;
+
+static SERDE_HELPERS_T: &str = r#"
+// Start serde helpers
+
+pub mod xdr_serde {
+    #[allow(unused_imports)]
+    use super::*;
+
+    const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    fn base64_value(c: u8) -> Option<u32> {
+        BASE64_CHARS.iter().position(|&b| b == c).map(|p| p as u32)
+    }
+
+    pub fn base64_encode(bytes: &[u8]) -> String {
+        let mut result = String::new();
+        for chunk in bytes.chunks(3) {
+            let n = chunk.iter().enumerate().fold(0u32, |acc, (i, &b)| acc | ((b as u32) << (16 - 8 * i)));
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    result.push(BASE64_CHARS[((n >> (18 - 6 * i)) & 63) as usize] as char);
+                } else {
+                    result.push('=');
+                }
+            }
+        }
+        result
+    }
+
+    pub fn base64_decode(value: &str) -> Option<Vec<u8>> {
+        let value = value.as_bytes();
+        if value.len() % 4 != 0 {
+            return None;
+        }
+        let mut result = Vec::new();
+        for chunk in value.chunks(4) {
+            let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+            if padding > 2 {
+                return None;
+            }
+            let mut n = 0u32;
+            for (i, &c) in chunk[..4 - padding].iter().enumerate() {
+                n |= base64_value(c)? << (18 - 6 * i);
+            }
+            for i in 0..3 - padding {
+                result.push((n >> (16 - 8 * i)) as u8);
+            }
+        }
+        Some(result)
+    }
+
+    /// Serializes opaque data as a base64 string, matching Go's []byte.
+    pub struct Base64<'a>(pub &'a [u8]);
+
+    impl<'a> serde::Serialize for Base64<'a> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&base64_encode(self.0))
+        }
+    }
+
+    pub mod base64 {
+        #[allow(unused_imports)]
+        use super::*;
+
+        pub fn serialize<S: serde::Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&base64_encode(value))
+        }
+
+        pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+        where
+            T: core::convert::TryFrom<Vec<u8>>,
+            D: serde::Deserializer<'de>,
+        {
+            let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+            let bytes = base64_decode(&value).ok_or_else(|| serde::de::Error::custom("invalid base64 data"))?;
+            T::try_from(bytes).map_err(|_| serde::de::Error::custom("invalid opaque length"))
+        }
+    }
+
+    /// Serializes fixed length arrays of any size as JSON arrays, matching Go's [N]T.
+    pub mod array {
+        #[allow(unused_imports)]
+        use super::*;
+
+        pub fn serialize<T: serde::Serialize, S: serde::Serializer, const N: usize>(
+            value: &[T; N],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(value.iter())
+        }
+
+        pub fn deserialize<'de, T, D, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
+        where
+            T: serde::Deserialize<'de>,
+            D: serde::Deserializer<'de>,
+        {
+            let value = <Vec<T> as serde::Deserialize>::deserialize(deserializer)?;
+            core::convert::TryFrom::try_from(value).map_err(|_| serde::de::Error::custom("invalid array length"))
+        }
+    }
+
+    /// Serializes 64 bit integers as strings, matching Go's `json:",string"`.
+    pub mod string {
+        #[allow(unused_imports)]
+        use super::*;
+
+        pub fn serialize<T: core::fmt::Display, S: serde::Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(value)
+        }
+
+        pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+        where
+            T: core::str::FromStr,
+            T::Err: core::fmt::Display,
+            D: serde::Deserializer<'de>,
+        {
+            let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+            value.parse().map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+// End serde helpers
+"#;
+
+static FIXED_ARRAY_T: &str = r#"
+#[allow(dead_code)]
+fn to_fixed_array<T, const N: usize>(val: Vec<T>) -> Result<[T; N], Error> {
+    core::convert::TryFrom::try_from(val).map_err(|_| Error::fixed_array_wrong_size())
+}
+
+#[allow(dead_code)]
+fn write_fixed_bytes<const N: usize>(val: &[u8; N], out: &mut Vec<u8>) -> Result<u64, Error> {
+    let padding = (4 - N % 4) % 4;
+    out.extend_from_slice(val);
+    out.extend_from_slice(&[0; 3][..padding]);
+    Ok((N + padding) as u64)
+}
+
+#[allow(dead_code)]
+fn read_fixed_bytes<const N: usize>(buffer: &[u8]) -> Result<([u8; N], u64), Error> {
+    let padded = N + (4 - N % 4) % 4;
+    if buffer.len() < padded {
+        return Err(Error::fixed_array_wrong_size());
+    }
+    let mut val = [0; N];
+    val.copy_from_slice(&buffer[..N]);
+    Ok((val, padded as u64))
+}
+
+#[allow(dead_code)]
+fn read_fixed_items<T: XDRIn + Default, const N: usize>(buffer: &[u8]) -> Result<([T; N], u64), Error> {
+    let mut val: [T; N] = core::array::from_fn(|_| T::default());
+    let mut read: u64 = 0;
+    for item in val.iter_mut() {
+        let rest = buffer.get(read as usize..).ok_or_else(Error::fixed_array_wrong_size)?;
+        let (t, len) = T::read_xdr(rest)?;
+        *item = t;
+        read += len;
+    }
+    Ok((val, read))
+}
+"#;
+
+static FIXED_ARRAY_JSON_T: &str = r#"
+#[allow(dead_code)]
+fn write_fixed_bytes_json<const N: usize>(val: &[u8; N], out: &mut Vec<u8>) -> Result<u64, Error> {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let start = out.len();
+    out.push(b'"');
+    if N <= 64 {
+        for byte in val {
+            out.push(HEX[(byte >> 4) as usize]);
+            out.push(HEX[(byte & 15) as usize]);
+        }
+    } else {
+        for chunk in val.chunks(3) {
+            let bits = chunk.iter().enumerate().fold(0, |acc, (i, b)| acc | (*b as usize) << (16 - 8 * i));
+            for i in 0..4 {
+                out.push(if i <= chunk.len() { BASE64[(bits >> (18 - 6 * i)) & 63] } else { b'=' });
+            }
+        }
+    }
+    out.push(b'"');
+    Ok((out.len() - start) as u64)
+}
+
+#[allow(dead_code)]
+fn write_fixed_items_json<T: XDROut>(val: &[T], out: &mut Vec<u8>) -> Result<u64, Error> {
+    let mut written: u64 = 2;
+    out.push(b'[');
+    for (i, item) in val.iter().enumerate() {
+        if i > 0 {
+            out.push(b',');
+            written += 1;
+        }
+        written += item.write_json(out)?;
+    }
+    out.push(b']');
+    Ok(written)
+}
+"#;
+
+static HEX_T: &str = r#"
+#[allow(dead_code)]
+fn hex_encode(val: &[u8], f: &mut core::fmt::Formatter) -> core::fmt::Result {
+    for byte in val {
+        write!(f, "{:02x}", byte)?;
+    }
+    Ok(())
+}
+
+#[allow(dead_code)]
+fn hex_decode(val: &str) -> Result<Vec<u8>, Error> {
+    if val.len() % 2 != 0 || !val.is_ascii() {
+        return Err(Error::string_bad_format());
+    }
+    (0..val.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&val[i..i + 2], 16).map_err(|_| Error::string_bad_format()))
+        .collect()
+}
+"#;
+
+static VALIDATE_HELPERS_T: &str = r#"
+// Start validation helpers
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum ValidationErrorKind {
+    TooLong { max: u32, len: usize },
+    WrongLength { expected: u32, len: usize },
+}
+
+/// A length bound violation, with the path of the offending field, e.g.
+/// `action[2].channelId`.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ValidationError {
+    pub path: String,
+    pub kind: ValidationErrorKind,
+}
+
+impl ValidationError {
+    fn new(path: &str, kind: ValidationErrorKind) -> Self {
+        ValidationError {
+            path: String::from(path),
+            kind,
+        }
+    }
+
+    fn at(mut self, name: &str) -> Self {
+        if !self.path.is_empty() && !self.path.starts_with('[') {
+            self.path.insert(0, '.');
+        }
+        self.path.insert_str(0, name);
+        self
+    }
+}
 
-{{#each this as |ns| ~}}
+impl core::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self.kind {
+            ValidationErrorKind::TooLong { max, len } => {
+                write!(f, "{}: length {} exceeds maximum of {}", self.path, len, max)
+            }
+            ValidationErrorKind::WrongLength { expected, len } => {
+                write!(f, "{}: length {} does not match required {}", self.path, len, expected)
+            }
+        }
+    }
+}
+
+pub trait Validate {
+    /// Checks every string, opaque and array length bound, recursively.
+    fn validate(&self) -> Result<(), ValidationError>;
+}
+
+#[allow(dead_code)]
+fn validate_len(path: &str, len: usize, size: u32, fixed: bool) -> Result<(), ValidationError> {
+    match fixed {
+        true if len != size as usize => Err(ValidationError::new(
+            path,
+            ValidationErrorKind::WrongLength { expected: size, len },
+        )),
+        false if len > size as usize => Err(ValidationError::new(
+            path,
+            ValidationErrorKind::TooLong { max: size, len },
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[allow(dead_code)]
+fn validate_items<T: Validate>(items: &[T]) -> Result<(), ValidationError> {
+    for (i, item) in items.iter().enumerate() {
+        let mut index = String::from("[");
+        index.push_str(&i.to_string());
+        index.push(']');
+        item.validate().map_err(|e| e.at(&index))?;
+    }
+    Ok(())
+}
+
+// End validation helpers
+"#;
+
+static NAMESPACE_START: &str = r#"
+{{#each namespaces as |ns| ~}}
 // Namespace start {{ns.name}}
 "#;
 
@@ -31,112 +564,1151 @@ static TYPEDEFS_T: &str = r#"
 // Start typedef section
 
 {{#each ns.typedefs as |td| ~}}
-#[derive(PartialEq, Clone, Default, Debug, XDROut, XDRIn)]
+{{#if td.def.comment}}
+{{doc td.def.comment "/// "}}
+{{/if}}
+#[derive(PartialEq, Clone{{#unless @root.options.fixed_arrays}}, Default{{/unless}}, Debug{{#unless @root.codec}}, XDROut, XDRIn{{/unless}}{{#if @root.options.serde}}, serde::Serialize, serde::Deserialize{{/if}})]
+{{#if @root.options.serde}}
+#[serde(transparent)]
+{{/if}}
 pub struct {{td.def.name}} {
+{{#if @root.options.serde}}
+  {{serde-typedef td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays}}
+{{/if}}
 {{#if td.def.array_size}}
+{{#unless @root.codec}}
 {{#if td.def.fixed_array}}
   #[array(fixed = {{td.def.array_size}})]
 {{else}}
   #[array(var = {{td.def.array_size}})]
 {{/if}}
-  pub t: {{#if (neqstr td.def.type_name) }}Vec<{{td.def.type_name}}>{{else}} {{td.def.type_name}} {{/if}},
+{{/unless}}
+{{/if}}
+  pub t: {{rust-type td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays}},
+}
+{{#if @root.options.fixed_arrays}}
+
+impl Default for {{td.def.name}} {
+    fn default() -> Self {
+        {{td.def.name}} {
+            t: {{default-value td.def.type_name td.def.array_size td.def.fixed_array true}},
+        }
+    }
+}
+{{/if}}
+{{/each}}
+// End typedef section
+"#;
+
+static NEWTYPE_T: &str = r#"
+// Start typedef conversions section
+{{#each ns.typedefs as |td|}}
+{{#if (bounded td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays)}}
+
+impl core::convert::TryFrom<{{rust-type td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays}}> for {{td.def.name}} {
+    type Error = Error;
+
+    fn try_from(t: {{rust-type td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays}}) -> Result<Self, Self::Error> {
+{{#if (fixedlen td.def.type_name td.def.fixed_array)}}
+        if t.len() != {{td.def.array_size}} {
+            return Err(Error::fixed_array_wrong_size());
+        }
 {{else}}
-  pub t:  {{td.def.type_name}},
+        if t.len() > {{td.def.array_size}} {
+            return Err(Error::var_array_wrong_size());
+        }
 {{/if}}
+        Ok({{td.def.name}} { t })
+    }
+}
+{{else}}
+
+impl From<{{rust-type td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays}}> for {{td.def.name}} {
+    fn from(t: {{rust-type td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays}}) -> Self {
+        {{td.def.name}} { t }
+    }
+}
+{{/if}}
+
+impl From<{{td.def.name}}> for {{rust-type td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays}} {
+    fn from(val: {{td.def.name}}) -> Self {
+        val.t
+    }
+}
+
+impl core::ops::Deref for {{td.def.name}} {
+    type Target = {{rust-type td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays}};
+
+    fn deref(&self) -> &Self::Target {
+        &self.t
+    }
+}
+
+{{#unless (bounded td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays)}}
+
+impl core::ops::DerefMut for {{td.def.name}} {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.t
+    }
+}
+{{/unless}}
+
+impl AsRef<{{rust-type td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays}}> for {{td.def.name}} {
+    fn as_ref(&self) -> &{{rust-type td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays}} {
+        &self.t
+    }
+}
+{{#if td.def.array_size}}{{#if (neqstr td.def.type_name)}}
+
+impl AsRef<[{{td.def.type_name}}]> for {{td.def.name}} {
+    fn as_ref(&self) -> &[{{td.def.type_name}}] {
+        &self.t[..]
+    }
+}
+
+impl core::convert::TryFrom<&[{{td.def.type_name}}]> for {{td.def.name}} {
+    type Error = Error;
+
+    fn try_from(val: &[{{td.def.type_name}}]) -> Result<Self, Self::Error> {
+{{#if td.def.fixed_array}}
+        if val.len() != {{td.def.array_size}} {
+            return Err(Error::fixed_array_wrong_size());
+        }
+{{else}}
+        if val.len() > {{td.def.array_size}} {
+            return Err(Error::var_array_wrong_size());
+        }
+{{/if}}
+{{#if (array td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays)}}
+        Ok({{td.def.name}} { t: to_fixed_array(val.to_vec())? })
+{{else}}
+        Ok({{td.def.name}} { t: val.to_vec() })
+{{/if}}
+    }
+}
+{{#if (base64 td.def.type_name td.def.array_size)}}
+
+impl core::fmt::Display for {{td.def.name}} {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        hex_encode(&self.t[..], f)
+    }
+}
+
+impl core::str::FromStr for {{td.def.name}} {
+    type Err = Error;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        core::convert::TryFrom::try_from(&hex_decode(val)?[..])
+    }
+}
+{{/if}}
+{{/if}}{{/if}}
+{{/each~}}
+// End typedef conversions section
+"#;
+
+static VALIDATE_T: &str = r#"
+// Start validation section
+{{#each ns.typedefs as |td|}}
+
+impl {{td.def.name}} {
+    pub fn new(t: {{rust-type td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays}}) -> Result<Self, ValidationError> {
+        let val = {{td.def.name}} { t };
+        val.validate()?;
+        Ok(val)
+    }
+}
+
+impl Validate for {{td.def.name}} {
+    fn validate(&self) -> Result<(), ValidationError> {
+        {{validate "self.t" "" td.def.type_name td.def.array_size td.def.fixed_array}}
+        Ok(())
+    }
+}
+{{/each}}
+{{#each ns.structs as |st|}}
+
+impl {{st.name}} {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new({{#each st.props as |prop|}}{{#if @index}}, {{/if}}{{prop.name}}: {{rust-type prop.type_name prop.array_size prop.fixed_array @root.options.fixed_arrays}}{{/each}}) -> Result<Self, ValidationError> {
+        let val = {{st.name}} {
+{{#each st.props as |prop|}}
+            {{prop.name}},
+{{/each}}
+        };
+        val.validate()?;
+        Ok(val)
+    }
+}
+
+impl Validate for {{st.name}} {
+    fn validate(&self) -> Result<(), ValidationError> {
+{{#each st.props as |prop|}}
+        {{validate (field "self" prop.name) prop.name prop.type_name prop.array_size prop.fixed_array}}
+{{/each}}
+        Ok(())
+    }
+}
+{{/each}}
+{{#each ns.enums as |enum|}}
+
+impl Validate for {{enum.name}} {
+    fn validate(&self) -> Result<(), ValidationError> {
+        Ok(())
+    }
+}
+{{/each}}
+{{#each ns.unions as |uni|}}
+
+impl Validate for {{uni.name}} {
+    fn validate(&self) -> Result<(), ValidationError> {
+        match self {
+{{#each uni.switch.cases as |case|}}
+{{#if (checked case.ret_type.type_name case.ret_type.array_size)}}
+            {{uni.name}}::{{case.value}}(val) => {
+                {{validate "val" case.ret_type.name case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array}}
+            }
+{{else}}
+            {{uni.name}}::{{case.value}}(_) => {}
+{{/if}}
+{{/each}}
+        }
+        Ok(())
+    }
+}
+{{/each~}}
+// End validation section
+"#;
+
+static STRUCTS_T: &str = r#"
+// Start struct section
+{{#each ns.structs as |st|}}
+
+{{#if st.comment}}
+{{doc st.comment "/// "}}
+{{/if}}
+#[derive(PartialEq, Clone{{#unless @root.options.fixed_arrays}}, Default{{/unless}}, Debug{{#unless @root.codec}}, XDROut, XDRIn{{/unless}}{{#if @root.options.serde}}, serde::Serialize, serde::Deserialize{{/if}})]
+pub struct {{st.name}} {
+{{#each st.props as |prop|}}
+{{#if prop.comment}}
+{{doc prop.comment "  /// "}}
+{{/if}}
+{{#if @root.options.serde}}
+  {{serde-field prop.name prop.type_name prop.array_size prop.fixed_array @root.options.fixed_arrays}}
+{{/if}}
+{{#if prop.array_size}}
+{{#unless @root.codec}}
+{{#if prop.fixed_array}}
+  #[array(fixed = {{prop.array_size}})]
+{{else}}
+  #[array(var = {{prop.array_size}})]
+{{/if}}
+{{/unless}}
+{{/if}}
+  pub {{prop.name}}: {{rust-type prop.type_name prop.array_size prop.fixed_array @root.options.fixed_arrays}},
+{{/each~}}
+}
+{{#if @root.options.fixed_arrays}}
+
+impl Default for {{st.name}} {
+    fn default() -> Self {
+        {{st.name}} {
+{{#each st.props as |prop|}}
+            {{prop.name}}: {{default-value prop.type_name prop.array_size prop.fixed_array true}},
+{{/each}}
+        }
+    }
+}
+{{/if}}
+{{/each}}
+// End struct section
+"#;
+
+static ENUM_T: &str = r#"
+{{#each ns.enums as |enum|}}
+{{#if enum.comment}}
+{{doc enum.comment "/// "}}
+{{/if}}
+#[derive(PartialEq, Clone, Debug{{#unless @root.codec}}, XDROut, XDRIn{{/unless}})]
+pub enum {{enum.name}} {
+{{#each enum.values as |val|~}}
+{{#if val.comment}}
+{{doc val.comment "    /// "}}
+{{/if}}
+    {{val.name}} = {{val.index}},
+{{/each~}}
+}
+
+impl Default for {{enum.name}} {
+    fn default() -> Self {
+        {{enum.name}}::{{enum.values.0.name}}
+    }
+}
+{{/each~}}
+"#;
+
+static UNION_T: &str = r#"
+// Start union section
+
+{{#each ns.unions as |uni|}}
+{{#if uni.comment}}
+{{doc uni.comment "/// "}}
+{{/if}}
+#[derive(PartialEq, Clone, Debug{{#unless @root.codec}}, XDROut, XDRIn{{/unless}})]
+pub enum {{uni.name}} {
+{{#each uni.switch.cases as |case|}}
+{{#if case.comment}}
+{{doc case.comment "    /// "}}
+{{/if}}
+{{#if case.ret_type.comment}}
+{{doc case.ret_type.comment "    /// "}}
+{{/if}}
+{{#if (not (isvoid case.ret_type.name))}}
+{{#if (neqstr case.ret_type.type_name)}}{{#if case.ret_type.array_size}}
+{{#unless @root.codec}}
+{{#if case.ret_type.fixed_array}}
+    #[array(fixed = {{case.ret_type.array_size}})]
+{{else}}
+    #[array(var = {{case.ret_type.array_size}})]
+{{/if}}
+{{/unless}}
+{{/if}}{{/if}}
+    {{case.value}}({{rust-type case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array @root.options.fixed_arrays}}),
+{{else}}
+    {{case.value}}(()),
+{{/if}}
+{{/each~}}
+}
+
+impl Default for {{uni.name}} {
+    fn default() -> Self {
+    {{#if (not (isvoid uni.switch.cases.0.ret_type.name))}}
+      {{uni.name}}::{{uni.switch.cases.0.value}}({{default-value uni.switch.cases.0.ret_type.type_name uni.switch.cases.0.ret_type.array_size uni.switch.cases.0.ret_type.fixed_array @root.options.fixed_arrays}})
+    {{else}}
+      {{uni.name}}::{{uni.switch.cases.0.value}}(())
+    {{/if}}
+    }
+}
+{{/each~}}
+// End union section
+"#;
+
+static CODEC_T: &str = r#"
+// Start codec section
+{{#each ns.typedefs as |td|}}
+
+impl XDROut for {{td.def.name}} {
+    fn write_xdr(&self, out: &mut Vec<u8>) -> Result<u64, Error> {
+        {{xdr-write "self.t" td.def.type_name td.def.array_size td.def.fixed_array @root.options.no_std @root.options.fixed_arrays}}
+    }
+{{#unless @root.options.no_std}}
+
+    fn write_json(&self, out: &mut Vec<u8>) -> Result<u64, Error> {
+        {{json-write "self.t" td.def.type_name td.def.array_size td.def.fixed_array}}
+    }
+{{/unless}}
+}
+
+impl XDRIn for {{td.def.name}} {
+    fn read_xdr(buffer: &[u8]) -> Result<(Self, u64), Error> {
+        let t = {{xdr-read "buffer" td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays}}?;
+        Ok(({{td.def.name}} { t: t.0 }, t.1))
+    }
+{{#unless @root.options.no_std}}
+
+    fn read_json(jval: json::JsonValue) -> Result<Self, Error> {
+        Ok({{td.def.name}} {
+            t: {{json-read "jval" td.def.type_name td.def.array_size td.def.fixed_array}}?,
+        })
+    }
+{{/unless}}
+}
+{{/each}}
+{{#each ns.structs as |st|}}
+
+impl XDROut for {{st.name}} {
+    fn write_xdr(&self, out: &mut Vec<u8>) -> Result<u64, Error> {
+        let mut written: u64 = 0;
+{{#each st.props as |prop|}}
+        written += {{xdr-write (field "self" prop.name) prop.type_name prop.array_size prop.fixed_array @root.options.no_std @root.options.fixed_arrays}}?;
+{{/each}}
+        Ok(written)
+    }
+{{#unless @root.options.no_std}}
+
+    fn write_json(&self, out: &mut Vec<u8>) -> Result<u64, Error> {
+        let mut written = out.write(b"{").unwrap() as u64;
+{{#each st.props as |prop|}}
+{{#if @index}}
+        written += out.write(b",").unwrap() as u64;
+{{/if}}
+        written += out.write(b"\"{{prop.name}}\":").unwrap() as u64;
+        written += {{json-write (field "self" prop.name) prop.type_name prop.array_size prop.fixed_array}}?;
+{{/each}}
+        written += out.write(b"}").unwrap() as u64;
+        Ok(written)
+    }
+{{/unless}}
+}
+
+impl XDRIn for {{st.name}} {
+    fn read_xdr(buffer: &[u8]) -> Result<(Self, u64), Error> {
+        let mut read: u64 = 0;
+{{#each st.props as |prop|}}
+        let {{prop.name}}_result = {{xdr-read "&buffer[read as usize..]" prop.type_name prop.array_size prop.fixed_array @root.options.fixed_arrays}}?;
+        read += {{prop.name}}_result.1;
+{{/each}}
+        Ok((
+            {{st.name}} {
+{{#each st.props as |prop|}}
+                {{prop.name}}: {{prop.name}}_result.0,
+{{/each}}
+            },
+            read,
+        ))
+    }
+{{#unless @root.options.no_std}}
+
+    fn read_json(jval: json::JsonValue) -> Result<Self, Error> {
+        let obj = match &jval {
+            json::JsonValue::Object(o) => o,
+            _ => return Err(Error::invalid_json()),
+        };
+        Ok({{st.name}} {
+{{#each st.props as |prop|}}
+            {{prop.name}}: {{json-read (json-field "obj" prop.name) prop.type_name prop.array_size prop.fixed_array}}?,
+{{/each}}
+        })
+    }
+{{/unless}}
+}
+{{/each}}
+{{#each ns.enums as |enum|}}
+
+impl XDROut for {{enum.name}} {
+    fn write_xdr(&self, out: &mut Vec<u8>) -> Result<u64, Error> {
+        (self.clone() as i32).write_xdr(out)
+    }
+{{#unless @root.options.no_std}}
+
+    fn write_json(&self, out: &mut Vec<u8>) -> Result<u64, Error> {
+        (self.clone() as i32).write_json(out)
+    }
+{{/unless}}
+}
+
+impl XDRIn for {{enum.name}} {
+    fn read_xdr(buffer: &[u8]) -> Result<(Self, u64), Error> {
+        match i32::read_xdr(buffer)? {
+{{#each enum.values as |val|}}
+            ({{val.index}}, read) => Ok(({{enum.name}}::{{val.name}}, read)),
+{{/each}}
+            _ => Err(Error::invalid_enum_value()),
+        }
+    }
+{{#unless @root.options.no_std}}
+
+    fn read_json(jval: json::JsonValue) -> Result<Self, Error> {
+        match i32::read_json(jval)? {
+{{#each enum.values as |val|}}
+            {{val.index}} => Ok({{enum.name}}::{{val.name}}),
+{{/each}}
+            _ => Err(Error::invalid_enum_value()),
+        }
+    }
+{{/unless}}
+}
+{{/each}}
+{{#each ns.unions as |uni|}}
+
+impl XDROut for {{uni.name}} {
+    fn write_xdr(&self, out: &mut Vec<u8>) -> Result<u64, Error> {
+        match self {
+{{#each uni.switch.cases as |case|}}
+            {{uni.name}}::{{case.value}}(val) => {
+                let written = {{uni.switch.enum_type}}::{{case.value}}.write_xdr(out)?;
+{{#if (isvoid case.ret_type.name)}}
+                Ok(written + val.write_xdr(out)?)
+{{else}}
+                Ok(written + {{xdr-write "val" case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array @root.options.no_std @root.options.fixed_arrays}}?)
+{{/if}}
+            }
+{{/each}}
+        }
+    }
+{{#unless @root.options.no_std}}
+
+    fn write_json(&self, out: &mut Vec<u8>) -> Result<u64, Error> {
+        let mut written = out.write(b"{\"type\":").unwrap() as u64;
+        match self {
+{{#each uni.switch.cases as |case|}}
+            {{uni.name}}::{{case.value}}(val) => {
+                written += {{uni.switch.enum_type}}::{{case.value}}.write_json(out)?;
+                written += out.write(b",\"data\":").unwrap() as u64;
+{{#if (isvoid case.ret_type.name)}}
+                written += val.write_json(out)?;
+{{else}}
+                written += {{json-write "val" case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array}}?;
+{{/if}}
+            }
+{{/each}}
+        }
+        written += out.write(b"}").unwrap() as u64;
+        Ok(written)
+    }
+{{/unless}}
+}
+
+impl XDRIn for {{uni.name}} {
+    fn read_xdr(buffer: &[u8]) -> Result<(Self, u64), Error> {
+        let (kind, read) = {{uni.switch.enum_type}}::read_xdr(buffer)?;
+        #[allow(unreachable_patterns)]
+        match kind {
+{{#each uni.switch.cases as |case|}}
+            {{uni.switch.enum_type}}::{{case.value}} => {
+{{#if (isvoid case.ret_type.name)}}
+                Ok(({{uni.name}}::{{case.value}}(()), read))
+{{else}}
+                let val = {{xdr-read "&buffer[read as usize..]" case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array @root.options.fixed_arrays}}?;
+                Ok(({{uni.name}}::{{case.value}}(val.0), read + val.1))
+{{/if}}
+            }
+{{/each}}
+            _ => Err(Error::invalid_enum_value()),
+        }
+    }
+{{#unless @root.options.no_std}}
+
+    fn read_json(jval: json::JsonValue) -> Result<Self, Error> {
+        let obj = match &jval {
+            json::JsonValue::Object(o) => o,
+            _ => return Err(Error::invalid_json()),
+        };
+        let data = obj.get("data").cloned().unwrap_or(json::JsonValue::Null);
+        #[allow(unreachable_patterns)]
+        match {{uni.switch.enum_type}}::read_json(obj.get("type").cloned().unwrap_or(json::JsonValue::Null))? {
+{{#each uni.switch.cases as |case|}}
+{{#if (isvoid case.ret_type.name)}}
+            {{uni.switch.enum_type}}::{{case.value}} => Ok({{uni.name}}::{{case.value}}(())),
+{{else}}
+            {{uni.switch.enum_type}}::{{case.value}} => Ok({{uni.name}}::{{case.value}}(
+                {{json-read "data" case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array}}?,
+            )),
+{{/if}}
+{{/each}}
+            _ => Err(Error::invalid_enum_value()),
+        }
+    }
+{{/unless}}
+}
+{{/each}}
+// End codec section
+"#;
+
+static SERDE_T: &str = r#"
+// Start serde section
+{{#each ns.enums as |enum|}}
+
+impl serde::Serialize for {{enum.name}} {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32(self.clone() as i32)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for {{enum.name}} {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match <i32 as serde::Deserialize>::deserialize(deserializer)? {
+{{#each enum.values as |val|}}
+            {{val.index}} => Ok({{enum.name}}::{{val.name}}),
+{{/each}}
+            v => Err(serde::de::Error::invalid_value(
+                serde::de::Unexpected::Signed(v as i64),
+                &"a valid {{enum.name}} value",
+            )),
+        }
+    }
+}
+{{/each}}
+{{#each ns.unions as |uni|}}
+
+impl serde::Serialize for {{uni.name}} {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("{{uni.name}}", 2)?;
+        match self {
+{{#each uni.switch.cases as |case|}}
+{{#if (isvoid case.ret_type.name)}}
+            {{uni.name}}::{{case.value}}(()) => {
+                state.serialize_field("type", &({{uni.switch.enum_type}}::{{case.value}} as i32))?;
+                state.serialize_field("data", "")?;
+            }
+{{else}}
+            {{uni.name}}::{{case.value}}(val) => {
+                state.serialize_field("type", &({{uni.switch.enum_type}}::{{case.value}} as i32))?;
+{{#if (base64 case.ret_type.type_name case.ret_type.array_size)}}
+                state.serialize_field("data", &xdr_serde::Base64(val))?;
+{{else}}{{#if case.ret_type.array_size}}
+                state.serialize_field("data", &val[..])?;
+{{else}}
+                state.serialize_field("data", val)?;
+{{/if}}{{/if}}
+            }
+{{/if}}
+{{/each}}
+        }
+        state.end()
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for {{uni.name}} {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let mut raw = <serde_json::Value as serde::Deserialize>::deserialize(deserializer)?;
+        let kind = raw
+            .get("type")
+            .and_then(serde_json::Value::as_i64)
+            .ok_or_else(|| D::Error::missing_field("type"))?;
+        let data = raw.get_mut("data").map(serde_json::Value::take).unwrap_or_default();
+        match kind {
+{{#each uni.switch.cases as |case|}}
+{{#if (isvoid case.ret_type.name)}}
+            k if k == {{uni.switch.enum_type}}::{{case.value}} as i64 => Ok({{uni.name}}::{{case.value}}(())),
+{{else}}
+            k if k == {{uni.switch.enum_type}}::{{case.value}} as i64 => {{serde-read case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array @root.options.fixed_arrays}}(data)
+                .map({{uni.name}}::{{case.value}})
+                .map_err(D::Error::custom),
+{{/if}}
+{{/each}}
+            _ => Err(D::Error::custom("invalid union type")),
+        }
+    }
+}
+{{/each}}
+// End serde section
+"#;
+
+static ARBITRARY_HELPERS_T: &str = r#"
+#[cfg(feature = "arbitrary")]
+#[allow(dead_code)]
+mod xdr_arbitrary {
+    #[allow(unused_imports)]
+    use super::*;
+    use arbitrary::{Arbitrary, Result, Unstructured};
+
+    pub fn len(u: &mut Unstructured, max: u32) -> Result<usize> {
+        let max = max.min(u.len() as u32);
+        Ok(u.int_in_range(0..=max)? as usize)
+    }
+
+    pub fn string(u: &mut Unstructured, max: u32) -> Result<String> {
+        let len = len(u, max)?;
+        (0..len).map(|_| Ok(char::from(u.int_in_range(b' '..=b'~')?))).collect()
+    }
+
+    pub fn fixed<'a, T: Arbitrary<'a>>(u: &mut Unstructured<'a>, size: u32) -> Result<Vec<T>> {
+        (0..size).map(|_| T::arbitrary(u)).collect()
+    }
+
+    pub fn var<'a, T: Arbitrary<'a>>(u: &mut Unstructured<'a>, max: u32) -> Result<Vec<T>> {
+        let len = len(u, max)?;
+        (0..len).map(|_| T::arbitrary(u)).collect()
+    }
+}
+"#;
+
+static ARBITRARY_T: &str = r#"
+// Start arbitrary section
+{{#each ns.typedefs as |td|}}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for {{td.def.name}} {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok({{td.def.name}} {
+            t: {{arbitrary td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays}},
+        })
+    }
+}
+{{/each}}
+{{#each ns.structs as |st|}}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for {{st.name}} {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok({{st.name}} {
+{{#each st.props as |prop|}}
+            {{prop.name}}: {{arbitrary prop.type_name prop.array_size prop.fixed_array @root.options.fixed_arrays}},
+{{/each}}
+        })
+    }
+}
+{{/each}}
+{{#each ns.enums as |enum|}}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for {{enum.name}} {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(match u.int_in_range(0..={{last enum.values}})? {
+{{#each enum.values as |val|}}
+            {{#if @last}}_{{else}}{{@index}}{{/if}} => {{enum.name}}::{{val.name}},
+{{/each}}
+        })
+    }
+}
+{{/each}}
+{{#each ns.unions as |uni|}}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for {{uni.name}} {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(match u.int_in_range(0..={{last uni.switch.cases}})? {
+{{#each uni.switch.cases as |case|}}
+{{#if (not (isvoid case.ret_type.name))}}
+            {{#if @last}}_{{else}}{{@index}}{{/if}} => {{uni.name}}::{{case.value}}({{arbitrary case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array @root.options.fixed_arrays}}),
+{{else}}
+            {{#if @last}}_{{else}}{{@index}}{{/if}} => {{uni.name}}::{{case.value}}(()),
+{{/if}}
+{{/each}}
+        })
+    }
 }
 {{/each}}
-// End typedef section
+// End arbitrary section
 "#;
 
-static STRUCTS_T: &str = r#"
-// Start struct section
+static FOOTER: &str = r#"
+// Namespace end {{ns.name}}
+{{/each~}}"#;
+
+static ROUND_TRIP_T: &str = r#"
+#[cfg(test)]
+mod round_trip {
+    use super::*;
+
+    /// Deterministic xorshift generator so the tests need no dependencies.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+
+        /// Picks a length up to `max`, keeping values small and cutting off
+        /// recursion.
+        fn len(&mut self, max: u32, depth: usize) -> u32 {
+            if depth > 3 {
+                return 0;
+            }
+            self.below(u64::from(max.min(8)) + 1) as u32
+        }
+
+        fn string(&mut self, max: u32, depth: usize) -> String {
+            (0..self.len(max, depth)).map(|_| char::from(b'a' + self.below(26) as u8)).collect()
+        }
+    }
+
+    trait Random {
+        fn random(rng: &mut Rng, depth: usize) -> Self;
+    }
+
+    macro_rules! random_int {
+        ($($t:ty),*) => {
+            $(impl Random for $t {
+                fn random(rng: &mut Rng, _depth: usize) -> Self {
+                    rng.next() as $t
+                }
+            })*
+        };
+    }
+
+    random_int!(u8, i32, u32, i64, u64);
+
+    impl Random for bool {
+        fn random(rng: &mut Rng, _depth: usize) -> Self {
+            rng.below(2) == 1
+        }
+    }
+
+    impl Random for f32 {
+        fn random(rng: &mut Rng, _depth: usize) -> Self {
+            rng.below(1 << 20) as f32 / 1024.0
+        }
+    }
+
+    impl Random for f64 {
+        fn random(rng: &mut Rng, _depth: usize) -> Self {
+            rng.below(1 << 40) as f64 / 1024.0
+        }
+    }
+{{#each namespaces as |ns|}}
+{{#each ns.typedefs as |td|}}
+
+    impl Random for {{td.def.name}} {
+        fn random(rng: &mut Rng, depth: usize) -> Self {
+            {{td.def.name}} {
+                t: {{random td.def.type_name td.def.array_size td.def.fixed_array @root.options.fixed_arrays}},
+            }
+        }
+    }
+{{/each}}
 {{#each ns.structs as |st|}}
 
-#[derive(PartialEq, Clone, Default, Debug, XDROut, XDRIn)]
-pub struct {{st.name}} {
+    impl Random for {{st.name}} {
+        fn random(rng: &mut Rng, depth: usize) -> Self {
+            {{st.name}} {
 {{#each st.props as |prop|}}
-{{#if prop.array_size}}
-{{#if prop.fixed_array}}
-  #[array(fixed = {{prop.array_size}})]
-{{else}}
-  #[array(var = {{prop.array_size}})]
-{{/if}}
-  pub {{prop.name}}: {{#if (neqstr prop.type_name) }}Vec<{{prop.type_name}}>{{else}} {{prop.type_name}} {{/if}},
+                {{prop.name}}: {{random prop.type_name prop.array_size prop.fixed_array @root.options.fixed_arrays}},
+{{/each}}
+            }
+        }
+    }
+{{/each}}
+{{#each ns.enums as |enum|}}
+
+    impl Random for {{enum.name}} {
+        fn random(rng: &mut Rng, _depth: usize) -> Self {
+            match rng.below({{len enum.values}}) {
+{{#each enum.values as |val|}}
+                {{#if @last}}_{{else}}{{@index}}{{/if}} => {{enum.name}}::{{val.name}},
+{{/each}}
+            }
+        }
+    }
+{{/each}}
+{{#each ns.unions as |uni|}}
+
+    impl Random for {{uni.name}} {
+        fn random(rng: &mut Rng, depth: usize) -> Self {
+            match rng.below({{len uni.switch.cases}}) {
+{{#each uni.switch.cases as |case|}}
+{{#if (not (isvoid case.ret_type.name))}}
+                {{#if @last}}_{{else}}{{@index}}{{/if}} => {{uni.name}}::{{case.value}}({{random case.ret_type.type_name case.ret_type.array_size case.ret_type.fixed_array @root.options.fixed_arrays}}),
 {{else}}
-  pub {{prop.name}}:  {{prop.type_name}},
+                {{#if @last}}_{{else}}{{@index}}{{/if}} => {{uni.name}}::{{case.value}}(()),
 {{/if}}
-{{/each~}}
-}
 {{/each}}
-// End struct section
-"#;
+            }
+        }
+    }
+{{/each}}
+{{/each}}
 
-static ENUM_T: &str = r#"
+    fn round_trip<T: Random + XDROut + XDRIn + PartialEq + core::fmt::Debug>() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..100 {
+            let val = T::random(&mut rng, 0);
+            let mut buffer = Vec::new();
+            let written = val.write_xdr(&mut buffer).unwrap();
+            assert_eq!(written as usize, buffer.len());
+            let (decoded, read) = T::read_xdr(&buffer).unwrap();
+            assert_eq!(read, written);
+            assert_eq!(decoded, val);
+        }
+    }
+{{#each namespaces as |ns|}}
+{{#each ns.typedefs as |td|}}
+
+    #[test]
+    fn round_trip_{{td.def.name}}() {
+        round_trip::<{{td.def.name}}>();
+    }
+{{/each}}
+{{#each ns.structs as |st|}}
+
+    #[test]
+    fn round_trip_{{st.name}}() {
+        round_trip::<{{st.name}}>();
+    }
+{{/each}}
 {{#each ns.enums as |enum|}}
-#[derive(PartialEq, Clone, Debug, XDROut, XDRIn)]
-pub enum {{enum.name}} {
-{{#each enum.values as |val|~}}
-    {{val.name}} = {{val.index}},
-{{/each~}}
-}
 
-impl Default for {{enum.name}} {
-    fn default() -> Self {
-        {{enum.name}}::{{enum.values.0.name}}
+    #[test]
+    fn round_trip_{{enum.name}}() {
+        round_trip::<{{enum.name}}>();
+    }
+{{/each}}
+{{#each ns.unions as |uni|}}
+
+    #[test]
+    fn round_trip_{{uni.name}}() {
+        round_trip::<{{uni.name}}>();
     }
+{{/each}}
+{{/each}}
 }
-{{/each~}}
 "#;
 
-static UNION_T: &str = r#"
-// Start union section
+#[derive(Debug, Default, Serialize)]
+pub struct RustGenerator {
+    pub include_macro: bool,
 
-{{#each ns.unions as |uni|}}
-#[derive(PartialEq, Clone, Debug, XDROut, XDRIn)]
-pub enum {{uni.name}} {
-{{#each uni.switch.cases as |case|}}
-{{#if (not (isvoid case.ret_type.name))}}
-    {{#if (eqstr case.ret_type.type_name)}}
-        {{case.value}}({{case.ret_type.type_name}}),
-    {{else}} {{#if case.ret_type.array_size}}
-        {{#if case.ret_type.fixed_array}}
-            #[array(fixed = {{case.ret_type.array_size}})]
-        {{else}}
-            #[array(var = {{case.ret_type.array_size}})]
-        {{/if}}
-        {{case.value}}(Vec<{{case.ret_type.type_name}}>),
-    {{else}}
-        {{case.value}}({{case.ret_type.type_name}}),
-    {{/if}} {{/if}}
-{{else}}
-  {{case.value}}(()),
-{{/if}}
-{{/each~}}
+    /// Emit `no_std` compatible code that encodes and decodes through a
+    /// generated runtime instead of xdr-rs-serialize.
+    pub no_std: bool,
+
+    /// Derive serde traits producing the same JSON shape as the Go generator.
+    pub serde: bool,
+
+    /// Represent fixed length arrays as `[T; N]` instead of `Vec<T>`.
+    pub fixed_arrays: bool,
+
+    /// Append a `#[cfg(test)]` module round-tripping random values of every
+    /// generated type.
+    pub tests: bool,
+
+    /// Implement `arbitrary::Arbitrary` behind the `arbitrary` cargo feature.
+    pub arbitrary: bool,
+
+    /// Generate conversions, `Deref` and hex `Display`/`FromStr` for typedefs.
+    pub newtypes: bool,
+
+    /// Generate validated constructors and `Validate` impls checking bounds.
+    pub validate: bool,
 }
 
-impl Default for {{uni.name}} {
-    fn default() -> Self {
-    {{#if (not (isvoid uni.switch.cases.0.ret_type.name))}}
-      {{uni.name}}::{{uni.switch.cases.0.value}}({{uni.switch.cases.0.ret_type.type_name}}::default())
-    {{else}}
-      {{uni.name}}::{{uni.switch.cases.0.value}}(())
-    {{/if}}
+#[derive(Serialize)]
+struct RenderData<'a> {
+    namespaces: Vec<Namespace>,
+
+    options: &'a RustGenerator,
+
+    /// Whether XDROut/XDRIn are implemented by the generated code rather
+    /// than derived.
+    codec: bool,
+}
+
+impl RustGenerator {
+    fn uses_codec(&self) -> bool {
+        self.no_std || self.fixed_arrays
+    }
+
+    fn build_file_template(&self) -> String {
+        let mut file_t = String::new();
+        if self.no_std {
+            file_t.push_str(NO_STD_HEADER);
+            file_t.push_str(RUNTIME_T);
+        } else {
+            file_t.push_str(HEADER);
+        }
+        if self.fixed_arrays {
+            file_t.push_str(FIXED_ARRAY_T);
+            if !self.no_std {
+                file_t.push_str(FIXED_ARRAY_JSON_T);
+            }
+        }
+        if self.serde {
+            file_t.push_str(SERDE_HELPERS_T);
+        }
+        if self.newtypes {
+            file_t.push_str(HEX_T);
+        }
+        if self.validate {
+            file_t.push_str(VALIDATE_HELPERS_T);
+        }
+        if self.arbitrary {
+            file_t.push_str(ARBITRARY_HELPERS_T);
+        }
+        file_t.push_str(NAMESPACE_START);
+        file_t.push_str(TYPEDEFS_T);
+        if self.newtypes {
+            file_t.push_str(NEWTYPE_T);
+        }
+        for section in &[STRUCTS_T, ENUM_T, UNION_T] {
+            file_t.push_str(section);
+        }
+        if self.validate {
+            file_t.push_str(VALIDATE_T);
+        }
+        if self.uses_codec() {
+            file_t.push_str(CODEC_T);
+        }
+        if self.serde {
+            file_t.push_str(SERDE_T);
+        }
+        if self.arbitrary {
+            file_t.push_str(ARBITRARY_T);
+        }
+        file_t.push_str(FOOTER);
+        file_t
     }
 }
-{{/each~}}
-// End union section
-"#;
 
-static FOOTER: &str = r#"
-// Namespace end {{ns.name}}
-{{/each~}}"#;
+fn is_opaque(typ: &str) -> bool {
+    typ == "u8"
+}
 
-fn build_file_template() -> String {
-    format!("{}{}{}{}{}{}", HEADER, TYPEDEFS_T, STRUCTS_T, ENUM_T, UNION_T, FOOTER)
+fn is_base64(typ: &str, size: i64) -> bool {
+    is_opaque(typ) && size > 0
 }
 
-pub struct RustGenerator {
-    pub include_macro: bool,
+fn is_array(typ: &str, size: i64, fixed: bool, arrays: bool) -> bool {
+    arrays && fixed && size > 0 && typ != "String"
+}
+
+/// Whether a declaration has a length bound its Rust type does not enforce.
+fn is_bounded(typ: &str, size: i64, fixed: bool, arrays: bool) -> bool {
+    size > 0 && size != i64::from(i32::MAX) && !is_array(typ, size, fixed, arrays)
+}
+
+fn rust_type(typ: &str, size: i64, fixed: bool, arrays: bool) -> String {
+    match (typ, size) {
+        (typ, 0) => typ.to_string(),
+        ("String", _) => "String".to_string(),
+        (typ, size) if is_array(typ, size, fixed, arrays) => format!("[{}; {}]", typ, size),
+        (typ, _) => format!("Vec<{}>", typ),
+    }
+}
+
+fn default_value(typ: &str, size: i64, fixed: bool, arrays: bool) -> String {
+    if is_array(typ, size, fixed, arrays) {
+        return "core::array::from_fn(|_| Default::default())".to_string();
+    }
+    "Default::default()".to_string()
+}
+
+fn serde_field(name: &str, typ: &str, size: i64, fixed: bool, arrays: bool) -> String {
+    let mut attrs = Vec::new();
+    let json_name = to_first_lower(name);
+    if json_name != name {
+        attrs.push(format!("rename = \"{}\"", json_name));
+    }
+    match (typ, size, fixed) {
+        ("i64", 0, _) | ("u64", 0, _) => attrs.push("with = \"xdr_serde::string\"".to_string()),
+        (typ, size, false) if is_base64(typ, size) => attrs.push("with = \"xdr_serde::base64\"".to_string()),
+        (typ, size, fixed) if is_array(typ, size, fixed, arrays) => attrs.push("with = \"xdr_serde::array\"".to_string()),
+        _ => {}
+    }
+    match attrs.len() {
+        0 => String::new(),
+        _ => format!("#[serde({})]", attrs.join(", ")),
+    }
+}
+
+fn serde_typedef(typ: &str, size: i64, fixed: bool, arrays: bool) -> String {
+    match (typ, size, fixed) {
+        (typ, size, false) if is_base64(typ, size) => "#[serde(with = \"xdr_serde::base64\")]".to_string(),
+        (typ, size, fixed) if is_array(typ, size, fixed, arrays) => "#[serde(with = \"xdr_serde::array\")]".to_string(),
+        _ => String::new(),
+    }
+}
+
+fn serde_read(typ: &str, size: i64, fixed: bool, arrays: bool) -> String {
+    match (typ, size, fixed) {
+        (typ, size, _) if is_base64(typ, size) => "xdr_serde::base64::deserialize",
+        (typ, size, fixed) if is_array(typ, size, fixed, arrays) => "xdr_serde::array::deserialize",
+        _ => "serde_json::from_value",
+    }
+    .to_string()
+}
+
+fn xdr_write(target: &str, typ: &str, size: i64, fixed: bool, no_std: bool, arrays: bool) -> String {
+    match (typ, size, fixed) {
+        (_, 0, _) => format!("{}.write_xdr(out)", target),
+        (typ, _, true) if arrays && is_opaque(typ) => format!("write_fixed_bytes(&{}, out)", target),
+        (typ, size, true) if is_opaque(typ) => format!("write_fixed_opaque(&{}, {}, out)", target, size),
+        (typ, size, false) if is_opaque(typ) => format!("write_var_opaque(&{}, {}, out)", target, size),
+        ("String", size, _) if !no_std => format!("write_var_string({}.clone(), {}, out)", target, size),
+        ("String", size, _) => format!("write_var_string(&{}, {}, out)", target, size),
+        (_, size, true) => format!("write_fixed_array(&{}, {}, out)", target, size),
+        (_, size, false) => format!("write_var_array(&{}, {}, out)", target, size),
+    }
+}
+
+fn xdr_read(buffer: &str, typ: &str, size: i64, fixed: bool, arrays: bool) -> String {
+    match (typ, size, fixed) {
+        (typ, 0, _) => format!("{}::read_xdr({})", typ, buffer),
+        (typ, size, fixed) if is_array(typ, size, fixed, arrays) && is_opaque(typ) => {
+            format!("read_fixed_bytes::<{}>({})", size, buffer)
+        }
+        (typ, size, fixed) if is_array(typ, size, fixed, arrays) => {
+            format!("read_fixed_items::<{}, {}>({})", typ, size, buffer)
+        }
+        (typ, size, true) if is_opaque(typ) => format!("read_fixed_opaque({}, {})", size, buffer),
+        (typ, size, false) if is_opaque(typ) => format!("read_var_opaque({}, {})", size, buffer),
+        ("String", size, _) => format!("read_var_string({}, {})", size, buffer),
+        (_, size, true) => format!("read_fixed_array({}, {})", size, buffer),
+        (_, size, false) => format!("read_var_array({}, {})", size, buffer),
+    }
+}
+
+fn json_write(target: &str, typ: &str, size: i64, fixed: bool) -> String {
+    match (typ, size, fixed) {
+        (_, 0, _) => format!("{}.write_json(out)", target),
+        (typ, _, true) if is_opaque(typ) => format!("write_fixed_bytes_json(&{}, out)", target),
+        (typ, size, false) if is_opaque(typ) => format!("write_var_opaque_json(&{}, {}, out)", target, size),
+        ("String", size, _) => format!("write_var_string_json({}.clone(), {}, out)", target, size),
+        (_, _, true) => format!("write_fixed_items_json(&{}, out)", target),
+        (_, size, false) => format!("write_var_array_json(&{}, {}, out)", target, size),
+    }
+}
+
+fn json_read(jval: &str, typ: &str, size: i64, fixed: bool) -> String {
+    match (typ, size, fixed) {
+        (typ, 0, _) => format!("{}::read_json({})", typ, jval),
+        (typ, size, true) if is_opaque(typ) => format!("read_fixed_opaque_json({}, {}).and_then(to_fixed_array)", size, jval),
+        (typ, size, false) if is_opaque(typ) => format!("read_var_opaque_json({}, {})", size, jval),
+        ("String", size, _) => format!("read_var_string_json({}, {})", size, jval),
+        (_, size, true) => format!("read_fixed_array_json({}, {}).and_then(to_fixed_array)", size, jval),
+        (_, size, false) => format!("read_var_array_json({}, {})", size, jval),
+    }
+}
+
+fn is_built_in(typ: &str) -> bool {
+    matches!(typ, "bool" | "u8" | "i32" | "u32" | "i64" | "u64" | "f32" | "f64" | "String")
+}
+
+fn is_checked(typ: &str, size: i64) -> bool {
+    size > 0 || !(typ.is_empty() || is_built_in(typ))
+}
+
+fn validate(target: &str, name: &str, typ: &str, size: i64, fixed: bool) -> String {
+    let mut checks = Vec::new();
+    if size > 0 {
+        checks.push(format!(
+            "validate_len(\"{}\", {}.len(), {}, {})?;",
+            name,
+            target,
+            size,
+            fixed && typ != "String"
+        ));
+    }
+    match (is_built_in(typ), size) {
+        (true, _) => {}
+        (false, 0) => checks.push(format!("{}.validate().map_err(|e| e.at(\"{}\"))?;", target, name)),
+        (false, _) => checks.push(format!("validate_items(&{}).map_err(|e| e.at(\"{}\"))?;", target, name)),
+    }
+    checks.join("\n        ")
+}
+
+fn random(typ: &str, size: i64, fixed: bool, arrays: bool) -> String {
+    match (typ, size, fixed) {
+        (typ, 0, _) => format!("{}::random(rng, depth + 1)", typ),
+        ("String", size, _) => format!("rng.string({}, depth)", size),
+        (typ, size, fixed) if is_array(typ, size, fixed, arrays) => {
+            format!("core::array::from_fn(|_| {}::random(rng, depth + 1))", typ)
+        }
+        (typ, size, true) => format!("(0..{}).map(|_| {}::random(rng, depth + 1)).collect()", size, typ),
+        (typ, size, false) => format!(
+            "(0..rng.len({}, depth)).map(|_| {}::random(rng, depth + 1)).collect()",
+            size, typ
+        ),
+    }
+}
+
+fn arbitrary(typ: &str, size: i64, fixed: bool, arrays: bool) -> String {
+    match (typ, size, fixed) {
+        (_, 0, _) => "u.arbitrary()?".to_string(),
+        ("String", size, _) => format!("xdr_arbitrary::string(u, {})?", size),
+        (typ, size, fixed) if is_array(typ, size, fixed, arrays) => "u.arbitrary()?".to_string(),
+        (_, size, true) => format!("xdr_arbitrary::fixed(u, {})?", size),
+        (_, size, false) => format!("xdr_arbitrary::var(u, {})?", size),
+    }
 }
 
 fn process_namespaces(namespaces: Vec<Namespace>) -> Result<Vec<Namespace>, &'static str> {
@@ -156,18 +1728,62 @@ fn process_namespaces(namespaces: Vec<Namespace>) -> Result<Vec<Namespace>, &'st
 impl CodeGenerator for RustGenerator {
     fn code(&self, namespaces: Vec<Namespace>) -> Result<String, &'static str> {
         let mut reg = Handlebars::new();
-        let file_t = build_file_template();
+        let file_t = self.build_file_template();
         handlebars_helper!(neqstr: |x: str| x != "String");
         handlebars_helper!(eqstr: |x: str| x == "String");
-        handlebars_helper!(isvoid: |x: str| x == "");
+        handlebars_helper!(isvoid: |x: str| x.is_empty());
+        handlebars_helper!(doc: |comment: str, prefix: str| doc_comment(comment, prefix));
+        reg.register_helper("doc", Box::new(doc));
         reg.register_helper("neqstr", Box::new(neqstr));
         reg.register_helper("eqstr", Box::new(eqstr));
+        handlebars_helper!(field: |owner: str, name: str| format!("{}.{}", owner, name));
+        handlebars_helper!(jsonfield: |obj: str, name: str| format!("{}.get(\"{}\").cloned().unwrap_or(json::JsonValue::Null)", obj, name));
+        handlebars_helper!(rusttype: |typ: str, size: i64, fixed: bool, arrays: bool| rust_type(typ, size, fixed, arrays));
+        handlebars_helper!(defaultvalue: |typ: str, size: i64, fixed: bool, arrays: bool| default_value(typ, size, fixed, arrays));
+        handlebars_helper!(xdrwrite: |target: str, typ: str, size: i64, fixed: bool, no_std: bool, arrays: bool| xdr_write(target, typ, size, fixed, no_std, arrays));
+        handlebars_helper!(xdrread: |buffer: str, typ: str, size: i64, fixed: bool, arrays: bool| xdr_read(buffer, typ, size, fixed, arrays));
+        handlebars_helper!(jsonwrite: |target: str, typ: str, size: i64, fixed: bool| json_write(target, typ, size, fixed));
+        handlebars_helper!(jsonread: |jval: str, typ: str, size: i64, fixed: bool| json_read(jval, typ, size, fixed));
+        handlebars_helper!(validatefield: |target: str, name: str, typ: str, size: i64, fixed: bool| validate(target, name, typ, size, fixed));
+        handlebars_helper!(checked: |typ: str, size: i64| is_checked(typ, size));
+        handlebars_helper!(bounded: |typ: str, size: i64, fixed: bool, arrays: bool| is_bounded(typ, size, fixed, arrays));
+        handlebars_helper!(fixedlen: |typ: str, fixed: bool| fixed && typ != "String");
+        handlebars_helper!(base64: |typ: str, size: i64| is_base64(typ, size));
+        handlebars_helper!(array: |typ: str, size: i64, fixed: bool, arrays: bool| is_array(typ, size, fixed, arrays));
+        handlebars_helper!(serdefield: |name: str, typ: str, size: i64, fixed: bool, arrays: bool| serde_field(name, typ, size, fixed, arrays));
+        handlebars_helper!(serdetypedef: |typ: str, size: i64, fixed: bool, arrays: bool| serde_typedef(typ, size, fixed, arrays));
+        handlebars_helper!(serderead: |typ: str, size: i64, fixed: bool, arrays: bool| serde_read(typ, size, fixed, arrays));
+        handlebars_helper!(randomvalue: |typ: str, size: i64, fixed: bool, arrays: bool| random(typ, size, fixed, arrays));
+        handlebars_helper!(arbitraryvalue: |typ: str, size: i64, fixed: bool, arrays: bool| arbitrary(typ, size, fixed, arrays));
+        handlebars_helper!(last: |items: array| items.len().saturating_sub(1));
         reg.register_helper("isvoid", Box::new(isvoid));
+        reg.register_helper("random", Box::new(randomvalue));
+        reg.register_helper("arbitrary", Box::new(arbitraryvalue));
+        reg.register_helper("last", Box::new(last));
+        reg.register_helper("field", Box::new(field));
+        reg.register_helper("json-field", Box::new(jsonfield));
+        reg.register_helper("rust-type", Box::new(rusttype));
+        reg.register_helper("default-value", Box::new(defaultvalue));
+        reg.register_helper("xdr-write", Box::new(xdrwrite));
+        reg.register_helper("xdr-read", Box::new(xdrread));
+        reg.register_helper("json-write", Box::new(jsonwrite));
+        reg.register_helper("json-read", Box::new(jsonread));
+        reg.register_helper("validate", Box::new(validatefield));
+        reg.register_helper("checked", Box::new(checked));
+        reg.register_helper("bounded", Box::new(bounded));
+        reg.register_helper("fixedlen", Box::new(fixedlen));
+        reg.register_helper("base64", Box::new(base64));
+        reg.register_helper("array", Box::new(array));
+        reg.register_helper("serde-field", Box::new(serdefield));
+        reg.register_helper("serde-typedef", Box::new(serdetypedef));
+        reg.register_helper("serde-read", Box::new(serderead));
+        reg.register_escape_fn(handlebars::no_escape);
         reg.register_helper(
             "macro-use",
             Box::new(
                 |_h: &Helper, _r: &Handlebars, _: &Context, _rc: &mut RenderContext, out: &mut dyn Output| -> HelperResult {
-                    if self.include_macro {
+                    // The built-in codec derives nothing from xdr_rs_serialize_derive.
+                    if self.include_macro && !self.uses_codec() {
                         out.write(
                             "#[macro_use]
 extern crate xdr_rs_serialize_derive;",
@@ -177,10 +1793,17 @@ extern crate xdr_rs_serialize_derive;",
                 },
             ),
         );
-        let processed_ns = process_namespaces(namespaces)?;
-        let result = reg.render_template(file_t.into_boxed_str().as_ref(), &processed_ns).unwrap();
+        let data = RenderData {
+            namespaces: process_namespaces(namespaces)?,
+            options: self,
+            codec: self.uses_codec(),
+        };
+        let mut result = reg.render_template(file_t.into_boxed_str().as_ref(), &data).unwrap();
+        if self.tests {
+            result.push_str(&reg.render_template(ROUND_TRIP_T, &data).unwrap());
+        }
 
-        return Ok(result);
+        Ok(result)
     }
 }
 
@@ -196,7 +1819,11 @@ mod tests {
             unions: Vec::new(),
             name: String::from("test"),
         }];
-        let res = RustGenerator { include_macro: false }.code(input_test);
+        let res = RustGenerator {
+            include_macro: false,
+            ..Default::default()
+        }
+        .code(input_test);
         assert!(res.is_ok());
         let generated_code = res.unwrap();
         println!("{}", generated_code);
@@ -212,7 +1839,11 @@ mod tests {
             unions: Vec::new(),
             name: String::from("test"),
         }];
-        let res = RustGenerator { include_macro: true }.code(input_test);
+        let res = RustGenerator {
+            include_macro: true,
+            ..Default::default()
+        }
+        .code(input_test);
         assert!(res.is_ok());
         let generated_code = res.unwrap();
         println!("{}", generated_code);
@@ -221,4 +1852,364 @@ mod tests {
 extern crate xdr_rs_serialize_derive;"
         ));
     }
+
+    #[test]
+    fn no_std() {
+        let input_test = vec![Namespace {
+            enums: Vec::new(),
+            structs: vec![Struct {
+                name: String::from("TestStruct"),
+                props: vec![Def {
+                    name: String::from("opaqueTest"),
+                    type_name: String::from("opaque"),
+                    array_size: 32,
+                    fixed_array: true,
+                    tag: String::new(),
+                    comment: String::new(),
+                }],
+                tag: String::new(),
+                comment: String::new(),
+            }],
+            typedefs: Vec::new(),
+            unions: Vec::new(),
+            name: String::from("test"),
+        }];
+        let res = RustGenerator {
+            no_std: true,
+            ..Default::default()
+        }
+        .code(input_test);
+        assert!(res.is_ok());
+        let generated_code = res.unwrap();
+        assert!(generated_code.contains("extern crate alloc;"));
+        assert!(!generated_code.contains("std::io::Write"));
+        assert!(!generated_code.contains("xdr_rs_serialize"));
+        assert!(!generated_code.contains("#[array("));
+        assert!(generated_code.contains("#[derive(PartialEq, Clone, Default, Debug)]"));
+        assert!(generated_code.contains("impl XDROut for TestStruct {"));
+        assert!(generated_code.contains("written += write_fixed_opaque(&self.opaqueTest, 32, out)?;"));
+        assert!(generated_code.contains("let opaqueTest_result = read_fixed_opaque(32, &buffer[read as usize..])?;"));
+    }
+
+    #[test]
+    fn serde() {
+        let input_test = vec![Namespace {
+            enums: Vec::new(),
+            structs: vec![Struct {
+                name: String::from("TestStruct"),
+                props: vec![
+                    Def {
+                        name: String::from("Nonce"),
+                        type_name: String::from("unsigned hyper"),
+                        array_size: 0,
+                        fixed_array: false,
+                        tag: String::new(),
+                        comment: String::new(),
+                    },
+                    Def {
+                        name: String::from("contract"),
+                        type_name: String::from("opaque"),
+                        array_size: 2147483647,
+                        fixed_array: false,
+                        tag: String::new(),
+                        comment: String::new(),
+                    },
+                ],
+                tag: String::new(),
+                comment: String::new(),
+            }],
+            typedefs: Vec::new(),
+            unions: Vec::new(),
+            name: String::from("test"),
+        }];
+        let res = RustGenerator {
+            serde: true,
+            ..Default::default()
+        }
+        .code(input_test);
+        assert!(res.is_ok());
+        let generated_code = res.unwrap();
+        assert!(generated_code.contains("pub mod xdr_serde {"));
+        assert!(generated_code.contains("XDROut, XDRIn, serde::Serialize, serde::Deserialize)]"));
+        assert!(generated_code.contains("#[serde(rename = \"nonce\", with = \"xdr_serde::string\")]"));
+        assert!(generated_code.contains("#[serde(with = \"xdr_serde::base64\")]"));
+    }
+
+    #[test]
+    fn fixed_arrays() {
+        let input_test = vec![Namespace {
+            enums: Vec::new(),
+            structs: vec![Struct {
+                name: String::from("TestStruct"),
+                props: vec![Def {
+                    name: String::from("hash"),
+                    type_name: String::from("opaque"),
+                    array_size: 32,
+                    fixed_array: true,
+                    tag: String::new(),
+                    comment: String::new(),
+                }],
+                tag: String::new(),
+                comment: String::new(),
+            }],
+            typedefs: Vec::new(),
+            unions: Vec::new(),
+            name: String::from("test"),
+        }];
+        let res = RustGenerator {
+            include_macro: true,
+            fixed_arrays: true,
+            ..Default::default()
+        }
+        .code(input_test);
+        assert!(res.is_ok());
+        let generated_code = res.unwrap();
+        assert!(!generated_code.contains("extern crate xdr_rs_serialize_derive;"));
+        assert!(generated_code.contains("pub hash: [u8; 32],"));
+        assert!(generated_code.contains("impl Default for TestStruct {"));
+        assert!(generated_code.contains("impl XDROut for TestStruct {"));
+        assert!(!generated_code.contains("#[array(fixed = 32)]"));
+        assert!(generated_code.contains("written += write_fixed_bytes(&self.hash, out)?;"));
+        assert!(generated_code.contains("written += write_fixed_bytes_json(&self.hash, out)?;"));
+        assert!(generated_code.contains("let hash_result = read_fixed_bytes::<32>(&buffer[read as usize..])?;"));
+        assert!(!generated_code.contains(".to_vec()"));
+    }
+
+    #[test]
+    fn newtype_conversions() {
+        let input_test = vec![Namespace {
+            enums: Vec::new(),
+            structs: Vec::new(),
+            typedefs: vec![Typedef {
+                def: Def {
+                    name: String::from("Hash"),
+                    type_name: String::from("opaque"),
+                    array_size: 32,
+                    fixed_array: true,
+                    tag: String::new(),
+                    comment: String::new(),
+                },
+            }],
+            unions: Vec::new(),
+            name: String::from("test"),
+        }];
+        let res = RustGenerator {
+            newtypes: true,
+            ..Default::default()
+        }
+        .code(input_test.clone());
+        assert!(res.is_ok());
+        let generated_code = res.unwrap();
+        assert!(generated_code.contains("impl core::convert::TryFrom<Vec<u8>> for Hash {"));
+        assert!(generated_code.contains("if t.len() != 32 {"));
+        assert!(!generated_code.contains("impl From<Vec<u8>> for Hash {"));
+        assert!(!generated_code.contains("impl core::ops::DerefMut for Hash {"));
+        assert!(generated_code.contains("impl From<Hash> for Vec<u8> {"));
+        assert!(generated_code.contains("impl core::ops::Deref for Hash {"));
+        assert!(generated_code.contains("impl core::convert::TryFrom<&[u8]> for Hash {"));
+        assert!(generated_code.contains("if val.len() != 32 {"));
+        assert!(generated_code.contains("impl core::fmt::Display for Hash {"));
+        assert!(generated_code.contains("impl core::str::FromStr for Hash {"));
+        let default_code = RustGenerator::default().code(input_test).unwrap();
+        assert!(!default_code.contains("impl core::ops::Deref for Hash {"));
+        assert!(!default_code.contains("fn hex_encode"));
+    }
+
+    #[test]
+    fn newtype_unbounded() {
+        let namespaces = build_namespaces(String::from(
+            "namespace test { typedef opaque Data<>; typedef string Name<8>; }",
+        ))
+        .unwrap();
+        let res = RustGenerator {
+            newtypes: true,
+            ..Default::default()
+        }
+        .code(namespaces);
+        let generated_code = res.unwrap();
+        assert!(generated_code.contains("impl From<Vec<u8>> for Data {"));
+        assert!(generated_code.contains("impl core::ops::DerefMut for Data {"));
+        assert!(generated_code.contains("impl core::convert::TryFrom<String> for Name {"));
+        assert!(generated_code.contains("if t.len() > 8 {"));
+        assert!(!generated_code.contains("impl core::ops::DerefMut for Name {"));
+    }
+
+    #[test]
+    fn validation() {
+        let input_test = vec![Namespace {
+            enums: Vec::new(),
+            structs: vec![Struct {
+                name: String::from("TestStruct"),
+                props: vec![
+                    Def {
+                        name: String::from("name"),
+                        type_name: String::from("string"),
+                        array_size: 256,
+                        fixed_array: false,
+                        tag: String::new(),
+                        comment: String::new(),
+                    },
+                    Def {
+                        name: String::from("children"),
+                        type_name: String::from("Child"),
+                        array_size: 4,
+                        fixed_array: false,
+                        tag: String::new(),
+                        comment: String::new(),
+                    },
+                ],
+                tag: String::new(),
+                comment: String::new(),
+            }],
+            typedefs: Vec::new(),
+            unions: Vec::new(),
+            name: String::from("test"),
+        }];
+        let res = RustGenerator {
+            validate: true,
+            ..Default::default()
+        }
+        .code(input_test.clone());
+        assert!(res.is_ok());
+        let generated_code = res.unwrap();
+        assert!(generated_code.contains("pub fn new(name: String, children: Vec<Child>) -> Result<Self, ValidationError> {"));
+        assert!(generated_code.contains("impl Validate for TestStruct {"));
+        assert!(generated_code.contains("validate_len(\"name\", self.name.len(), 256, false)?;"));
+        assert!(generated_code.contains("validate_items(&self.children).map_err(|e| e.at(\"children\"))?;"));
+        let default_code = RustGenerator::default().code(input_test).unwrap();
+        assert!(!default_code.contains("ValidationError"));
+    }
+
+    #[test]
+    fn validation_union_paths() {
+        let namespaces = build_namespaces(String::from(
+            "namespace test
+{
+    enum Kind
+    {
+        CALL = 0
+    };
+
+    struct Call
+    {
+        string function<8>;
+    };
+
+    union Action switch (Kind Type)
+    {
+        case CALL:
+            Call call;
+    }
+}",
+        ))
+        .unwrap();
+        let res = RustGenerator {
+            validate: true,
+            ..Default::default()
+        }
+        .code(namespaces);
+        let generated_code = res.unwrap();
+        assert!(generated_code.contains("val.validate().map_err(|e| e.at(\"call\"))?;"));
+        assert!(!generated_code.contains("e.at(\"CALL\")"));
+    }
+
+    #[test]
+    fn round_trip_tests() {
+        let input_test = vec![Namespace {
+            enums: vec![Enum {
+                name: String::from("Kind"),
+                values: vec![
+                    EnumValue {
+                        name: String::from("A"),
+                        index: 0,
+                        comment: String::new(),
+                    },
+                    EnumValue {
+                        name: String::from("B"),
+                        index: 1,
+                        comment: String::new(),
+                    },
+                ],
+                comment: String::new(),
+            }],
+            structs: vec![Struct {
+                name: String::from("TestStruct"),
+                props: vec![
+                    Def {
+                        name: String::from("kinds"),
+                        type_name: String::from("Kind"),
+                        array_size: 4,
+                        fixed_array: false,
+                        tag: String::new(),
+                        comment: String::new(),
+                    },
+                    Def {
+                        name: String::from("name"),
+                        type_name: String::from("string"),
+                        array_size: 16,
+                        fixed_array: false,
+                        tag: String::new(),
+                        comment: String::new(),
+                    },
+                ],
+                tag: String::new(),
+                comment: String::new(),
+            }],
+            typedefs: Vec::new(),
+            unions: Vec::new(),
+            name: String::from("test"),
+        }];
+        let res = RustGenerator {
+            tests: true,
+            arbitrary: true,
+            ..Default::default()
+        }
+        .code(input_test);
+        assert!(res.is_ok());
+        let generated_code = res.unwrap();
+        assert!(generated_code.contains("#[cfg(test)]\nmod round_trip {"));
+        assert!(generated_code.contains("kinds: (0..rng.len(4, depth)).map(|_| Kind::random(rng, depth + 1)).collect(),"));
+        assert!(generated_code.contains("name: rng.string(16, depth),"));
+        assert!(generated_code.contains("fn round_trip_TestStruct() {"));
+        assert!(generated_code.contains("impl<'a> arbitrary::Arbitrary<'a> for Kind {"));
+        assert!(generated_code.contains("Ok(match u.int_in_range(0..=1)? {"));
+        assert!(generated_code.contains("kinds: xdr_arbitrary::var(u, 4)?,"));
+    }
+
+    #[test]
+    fn comments() {
+        let input_test = vec![Namespace {
+            enums: vec![Enum {
+                name: String::from("Color"),
+                values: vec![EnumValue {
+                    name: String::from("RED"),
+                    index: 0,
+                    comment: String::from("The red color."),
+                }],
+                comment: String::new(),
+            }],
+            structs: vec![Struct {
+                name: String::from("TestStruct"),
+                props: vec![Def {
+                    name: String::from("count"),
+                    type_name: String::from("int"),
+                    array_size: 0,
+                    fixed_array: false,
+                    tag: String::new(),
+                    comment: String::from("Item count."),
+                }],
+                tag: String::new(),
+                comment: String::from("A test struct.\nSpans two lines."),
+            }],
+            typedefs: Vec::new(),
+            unions: Vec::new(),
+            name: String::from("test"),
+        }];
+        let res = RustGenerator::default().code(input_test);
+        assert!(res.is_ok());
+        let generated_code = res.unwrap();
+        assert!(generated_code.contains("/// A test struct.\n/// Spans two lines.\n#[derive("));
+        assert!(generated_code.contains("  /// Item count.\n  pub count: i32,\n"));
+        assert!(generated_code.contains("    /// The red color.\n    RED = 0,\n"));
+    }
 }
@@ -0,0 +1,14 @@
+extern crate pest;
+
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate handlebars;
+#[macro_use]
+extern crate pest_derive;
+
+pub mod ast;
+pub mod compat;
+pub mod generator;
+pub mod value;
+pub mod vectors;
@@ -1,27 +1,116 @@
-extern crate pest;
 extern crate structopt;
+extern crate xdr_codegen;
 extern crate xdr_rs_serialize;
 
-#[macro_use]
-extern crate serde_derive;
-#[macro_use]
-extern crate handlebars;
-#[macro_use]
-extern crate pest_derive;
-
 use std::path::PathBuf;
 use structopt::StructOpt;
+use xdr_codegen::{ast, compat, generator, value, vectors};
 use xdr_rs_serialize::ser::*;
 
 use std::fs::File;
 use std::io::{self, Read};
 
-mod ast;
-mod generator;
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Generate golden test vectors with hex and JSON encodings
+    #[structopt(name = "vectors")]
+    Vectors {
+        /// Input files, stdin if not present
+        #[structopt(parse(from_os_str))]
+        input: Vec<PathBuf>,
+
+        /// Vectors file, stdout if not present
+        #[structopt(short = "o", long = "output")]
+        output: Option<PathBuf>,
+
+        /// Number of vectors per type
+        #[structopt(long = "count", default_value = "3")]
+        count: u64,
+
+        /// Directory to write Go, Rust and JavaScript test harnesses to
+        #[structopt(long = "harness", parse(from_os_str))]
+        harness: Option<PathBuf>,
+
+        /// Go package name of the harness
+        #[structopt(long = "go-package", default_value = "xdr")]
+        go_package: String,
+
+        /// Module the JavaScript harness imports the generated code from
+        #[structopt(long = "js-module", default_value = "xdr")]
+        js_module: String,
+    },
+
+    /// Decode an XDR value using a schema
+    #[structopt(name = "decode")]
+    Decode {
+        /// Schema files
+        #[structopt(parse(from_os_str), raw(required = "true"))]
+        input: Vec<PathBuf>,
+
+        /// Type of the encoded value, optionally qualified by namespace
+        #[structopt(short = "t", long = "type")]
+        root: String,
+
+        /// File holding the encoded value, stdin if not present
+        #[structopt(short = "d", long = "data", parse(from_os_str))]
+        data: Option<PathBuf>,
+
+        /// Input encoding: bin, hex or base64
+        #[structopt(short = "f", long = "format", default_value = "hex")]
+        format: String,
+
+        /// Output style: json or tree
+        #[structopt(short = "s", long = "style", default_value = "json")]
+        style: String,
+    },
+
+    /// Encode a JSON or YAML value using a schema
+    #[structopt(name = "encode")]
+    Encode {
+        /// Schema files
+        #[structopt(parse(from_os_str), raw(required = "true"))]
+        input: Vec<PathBuf>,
+
+        /// Type of the value, optionally qualified by namespace
+        #[structopt(short = "t", long = "type")]
+        root: String,
+
+        /// File holding the JSON or YAML value, stdin if not present
+        #[structopt(short = "d", long = "data", parse(from_os_str))]
+        data: Option<PathBuf>,
+
+        /// Read the value as YAML
+        #[structopt(long = "yaml")]
+        yaml: bool,
+
+        /// Output encoding: bin, hex or base64
+        #[structopt(short = "f", long = "format", default_value = "hex")]
+        format: String,
+
+        /// Output file, stdout if not present
+        #[structopt(short = "o", long = "output", parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+
+    /// Report wire-breaking changes between two versions of a schema
+    #[structopt(name = "compat")]
+    Compat {
+        /// Old schema file
+        #[structopt(parse(from_os_str))]
+        old: PathBuf,
+
+        /// New schema file
+        #[structopt(parse(from_os_str))]
+        new: PathBuf,
+    },
+}
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "xdr-codegen", about = "CLI tool for generating xdr code.")]
 struct Opt {
+    #[structopt(subcommand)]
+    command: Option<Command>,
+
     /// Input files, stdin if not present
     #[structopt(parse(from_os_str))]
     input: Vec<PathBuf>,
@@ -37,55 +126,324 @@ struct Opt {
     /// Include macro_use for xdr_rs_serialize_derive crate in Rust
     #[structopt(short = "m", long = "macro")]
     include_macro: bool,
+
+    /// Generate no_std compatible Rust with a self-contained encoder
+    #[structopt(long = "no-std")]
+    no_std: bool,
+
+    /// Derive serde Serialize/Deserialize for Rust types (Go compatible JSON)
+    #[structopt(long = "serde")]
+    serde: bool,
+
+    /// Generate fixed length arrays as [T; N] in Rust
+    #[structopt(long = "fixed-arrays")]
+    fixed_arrays: bool,
+
+    /// Append round-trip tests over random values to the Rust output
+    #[structopt(long = "rust-tests")]
+    rust_tests: bool,
+
+    /// Implement arbitrary::Arbitrary for Rust types behind the `arbitrary` feature
+    #[structopt(long = "arbitrary")]
+    arbitrary: bool,
+
+    /// Generate From/TryFrom, Deref and hex Display/FromStr for Rust typedefs
+    #[structopt(long = "rust-newtypes")]
+    rust_newtypes: bool,
+
+    /// Generate validated constructors and Validate impls for Rust types
+    #[structopt(long = "rust-validate")]
+    rust_validate: bool,
+
+    /// Generate Go with a self-contained encoder instead of go-xdr
+    #[structopt(long = "standalone")]
+    standalone: bool,
+
+    /// Marshal Go enums to JSON by name
+    #[structopt(long = "go-enum-names")]
+    go_enum_names: bool,
+
+    /// Marshal Go unions to JSON keyed by case and arm name
+    #[structopt(long = "go-union-names")]
+    go_union_names: bool,
+
+    /// Generate Go Validate methods enforcing XDR bounds
+    #[structopt(long = "go-validate")]
+    go_validate: bool,
+
+    /// Generate Go Clone, Equal and Hash methods
+    #[structopt(long = "go-copy")]
+    go_copy: bool,
+
+    /// Go package name
+    #[structopt(long = "go-package")]
+    go_package: Option<String>,
+
+    /// Generate one Go package per namespace into the output directory,
+    /// importing cross-namespace references from this path prefix
+    #[structopt(long = "go-import-prefix")]
+    go_import_prefix: Option<String>,
+
+    /// Also write Go fuzz and round-trip tests to <output>_test.go
+    #[structopt(long = "go-tests")]
+    go_tests: bool,
+
+    /// Format of the docs language: markdown or html
+    #[structopt(long = "docs-format", default_value = "markdown")]
+    docs_format: String,
 }
 
-fn main() -> io::Result<()> {
+fn read_input(input: &[PathBuf]) -> io::Result<String> {
     let mut buffer = String::new();
-    let stdin = io::stdin();
-    let opt = Opt::from_args();
-
-    match opt.input.len() {
+    match input.len() {
         0 => {
+            let stdin = io::stdin();
             let mut handle = stdin.lock();
             handle.read_to_string(&mut buffer)?;
         }
         _ => {
-            for file in opt.input.iter() {
+            for file in input.iter() {
                 let mut f = File::open(file)?;
                 f.read_to_string(&mut buffer)?;
             }
         }
     }
+    Ok(buffer)
+}
+
+fn write_output(output: Option<PathBuf>, content: &str) -> io::Result<()> {
+    match output {
+        None => {
+            println!("{}", content);
+        }
+        Some(path) => {
+            let mut file = File::create(path.to_str().unwrap())?;
+            file.write_all(content.as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn read_data(data: Option<PathBuf>) -> io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    match data {
+        None => {
+            io::stdin().lock().read_to_end(&mut buffer)?;
+        }
+        Some(path) => {
+            File::open(path)?.read_to_end(&mut buffer)?;
+        }
+    }
+    Ok(buffer)
+}
+
+fn fail(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn check_option(kind: &str, value: &str, options: &[&str]) -> io::Result<()> {
+    match options.contains(&value) {
+        true => Ok(()),
+        false => Err(fail(format!("invalid {} {}, options: {}", kind, value, options.join(", ")))),
+    }
+}
+
+fn run_command(command: Command) -> io::Result<()> {
+    match command {
+        Command::Vectors {
+            input,
+            output,
+            count,
+            harness,
+            go_package,
+            js_module,
+        } => {
+            let namespaces = ast::build_namespaces(read_input(&input)?).map_err(|e| fail(e.to_string()))?;
+            let vectors = vectors::build(&namespaces, count).map_err(fail)?;
+            if let Some(dir) = harness {
+                std::fs::create_dir_all(&dir)?;
+                File::create(dir.join("vectors_test.go"))?.write_all(vectors::go_harness(&vectors, &go_package).as_bytes())?;
+                File::create(dir.join("vectors.rs"))?.write_all(vectors::rust_harness(&vectors).as_bytes())?;
+                File::create(dir.join("vectors.test.js"))?.write_all(vectors::js_harness(&vectors, &js_module).as_bytes())?;
+            }
+            write_output(output, &vectors::to_json(&vectors))
+        }
+        Command::Decode {
+            input,
+            root,
+            data,
+            format,
+            style,
+        } => {
+            check_option("format", &format, &["bin", "hex", "base64"])?;
+            check_option("style", &style, &["json", "tree"])?;
+            let namespaces = ast::build_namespaces(read_input(&input)?).map_err(|e| fail(e.to_string()))?;
+            let schema = value::Schema::new(&namespaces);
+            let raw = read_data(data)?;
+            let bytes = match format.as_ref() {
+                "bin" => Some(raw),
+                "hex" => value::hex_decode(String::from_utf8_lossy(&raw).trim()),
+                _ => value::base64_decode(String::from_utf8_lossy(&raw).trim()),
+            }
+            .ok_or_else(|| fail(format!("input is not valid {}", format)))?;
+            let decoded = schema.decode(&root, &bytes).map_err(fail)?;
+            let out = match style.as_ref() {
+                "tree" => format!("{}:{}", root, decoded.to_tree()),
+                _ => serde_json::to_string_pretty(&schema.to_json(&root, &decoded).map_err(fail)?).unwrap(),
+            };
+            println!("{}", out);
+            Ok(())
+        }
+        Command::Encode {
+            input,
+            root,
+            data,
+            yaml,
+            format,
+            output,
+        } => {
+            check_option("format", &format, &["bin", "hex", "base64"])?;
+            let namespaces = ast::build_namespaces(read_input(&input)?).map_err(|e| fail(e.to_string()))?;
+            let schema = value::Schema::new(&namespaces);
+            let raw = read_data(data)?;
+            let json: serde_json::Value = match yaml {
+                true => serde_yaml::from_slice(&raw).map_err(|e| fail(e.to_string()))?,
+                false => serde_json::from_slice(&raw).map_err(|e| fail(e.to_string()))?,
+            };
+            let value = schema.read_json(&root, &json).map_err(fail)?;
+            let bytes = schema.encode(&root, &value).map_err(fail)?;
+            match format.as_ref() {
+                "bin" => match output {
+                    None => io::stdout().write_all(&bytes),
+                    Some(path) => File::create(path)?.write_all(&bytes),
+                },
+                "hex" => write_output(output, &value::hex_encode(&bytes)),
+                _ => write_output(output, &value::base64_encode(&bytes)),
+            }
+        }
+        Command::Compat { old, new } => {
+            let old = ast::build_namespaces(read_input(&[old])?).map_err(|e| fail(e.to_string()))?;
+            let new = ast::build_namespaces(read_input(&[new])?).map_err(|e| fail(e.to_string()))?;
+            let changes = compat::check(&old, &new);
+            for change in &changes {
+                println!("{}", change);
+            }
+            if compat::is_breaking(&changes) {
+                eprintln!("schema change breaks the wire format");
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+    }
+}
 
-    let namespaces = ast::build_namespaces(buffer).unwrap();
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> io::Result<()> {
+    let opt = Opt::from_args();
+    if let Some(command) = opt.command {
+        return run_command(command);
+    }
+
+    let namespaces = ast::build_namespaces(read_input(&opt.input)?).unwrap();
 
     let rust_generator;
+    let docs_generator;
+    let go_generator = generator::go::GoGenerator {
+        standalone: opt.standalone,
+        enum_names: opt.go_enum_names,
+        union_names: opt.go_union_names,
+        validate: opt.go_validate,
+        copy: opt.go_copy,
+        package: opt.go_package,
+        import_prefix: opt.go_import_prefix,
+    };
+    let go = matches!(opt.language.as_deref(), None | Some("go"));
+    if go_generator.import_prefix.is_some() {
+        if !go {
+            return Err(fail(String::from("--go-import-prefix requires --language go")));
+        }
+        let dir = opt
+            .output
+            .ok_or_else(|| fail(String::from("--go-import-prefix requires an output directory")))?;
+        for (package, code) in go_generator.packages(namespaces).map_err(|e| fail(e.to_string()))? {
+            let package_dir = dir.join(&package);
+            std::fs::create_dir_all(&package_dir)?;
+            let mut file = File::create(package_dir.join(format!("{}.go", package)))?;
+            file.write_all(code.as_bytes())?;
+        }
+        return Ok(());
+    }
+    let documents = match opt.language.as_deref() {
+        Some("json-schema") if namespaces.len() > 1 => Some(generator::json_schema::JsonSchemaGenerator {}.documents(namespaces.clone())),
+        Some("proto") if namespaces.len() > 1 => Some(generator::proto::ProtoGenerator {}.documents(namespaces.clone())),
+        _ => None,
+    };
+    if let Some(documents) = documents {
+        let dir = opt.output.expect("several namespaces require an output directory");
+        std::fs::create_dir_all(&dir)?;
+        for (name, document) in documents.unwrap() {
+            let mut file = File::create(dir.join(name))?;
+            file.write_all(document.as_bytes())?;
+        }
+        return Ok(());
+    }
+    if opt.go_tests {
+        if !go {
+            return Err(fail(String::from("--go-tests requires --language go")));
+        }
+        let path = opt
+            .output
+            .as_ref()
+            .ok_or_else(|| fail(String::from("--go-tests requires an output file")))?;
+        let stem = path.file_stem().unwrap().to_str().unwrap();
+        let test_path = path.with_file_name(format!("{}_test.go", stem));
+        let mut file = File::create(test_path)?;
+        file.write_all(go_generator.tests(namespaces.clone()).map_err(|e| fail(e.to_string()))?.as_bytes())?;
+    }
+
     let generator: &dyn generator::CodeGenerator = match opt.language {
         Some(language) => match language.as_ref() {
-            "go" => &generator::go::GoGenerator {},
+            "go" => &go_generator,
             "js" => &generator::js::JsGenerator {},
             "rust" => {
                 rust_generator = generator::rust::RustGenerator {
                     include_macro: opt.include_macro,
+                    no_std: opt.no_std,
+                    serde: opt.serde,
+                    fixed_arrays: opt.fixed_arrays,
+                    tests: opt.rust_tests,
+                    arbitrary: opt.arbitrary,
+                    newtypes: opt.rust_newtypes,
+                    validate: opt.rust_validate,
                 };
                 &rust_generator
             }
             "commonjs" => &generator::commonjs::CommonJsGenerator {},
-            _ => panic!("Invalid language selection. Options: go, js, rust commonjs"),
+            "json-ast" => &generator::json_ast::JsonAstGenerator {},
+            "json-schema" => &generator::json_schema::JsonSchemaGenerator {},
+            "openapi" => &generator::openapi::OpenApiGenerator {},
+            "proto" => &generator::proto::ProtoGenerator {},
+            "docs" => {
+                docs_generator = generator::docs::DocsGenerator {
+                    html: match opt.docs_format.as_ref() {
+                        "markdown" => false,
+                        "html" => true,
+                        _ => panic!("Invalid docs format selection. Options: markdown, html"),
+                    },
+                };
+                &docs_generator
+            }
+            _ => panic!("Invalid language selection. Options: go, js, rust, commonjs, json-ast, json-schema, openapi, proto, docs"),
         },
-        _ => &generator::go::GoGenerator {},
+        _ => &go_generator,
     };
 
     let code = generator.code(namespaces).unwrap();
-    match opt.output {
-        None => {
-            println!("{}", code);
-        }
-        Some(path) => {
-            let mut file = File::create(path.to_str().unwrap())?;
-            file.write_all(code.as_bytes())?;
-        }
-    }
-
-    Ok(())
+    write_output(opt.output, &code)
 }
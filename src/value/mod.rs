@@ -0,0 +1,1162 @@
+use super::ast::*;
+use super::generator::to_first_lower;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// A value of some schema type, built and inspected at runtime.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Void,
+    Bool(bool),
+    Int(i32),
+    UInt(u32),
+    Hyper(i64),
+    UHyper(u64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    Opaque(Vec<u8>),
+    Array(Vec<Value>),
+    /// Enum value by name.
+    Enum(String),
+    /// Struct fields in declaration order.
+    Struct(Vec<(String, Value)>),
+    /// Union case value, the name of its arm (empty for void arms) and the
+    /// value of the arm.
+    Union(String, String, Box<Value>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Primitive {
+    Bool,
+    Int,
+    UInt,
+    Hyper,
+    UHyper,
+    Float,
+    Double,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Type<'a> {
+    Primitive(Primitive),
+    Typedef(&'a Typedef),
+    Struct(&'a Struct),
+    Enum(&'a Enum),
+    Union(&'a Union),
+}
+
+/// The wire layout of a declaration.
+#[derive(Debug, Clone, Copy)]
+enum Shape<'a> {
+    Void,
+    Scalar(&'a str),
+    String(u32),
+    FixedOpaque(u32),
+    VarOpaque(u32),
+    FixedArray(&'a str, u32),
+    VarArray(&'a str, u32),
+}
+
+fn shape(def: &Def) -> Shape<'_> {
+    let size = def.array_size as u32;
+    match (def.type_name.as_str(), size, def.fixed_array) {
+        ("", _, _) => Shape::Void,
+        ("string", 0, _) => Shape::String(u32::MAX),
+        ("string", size, _) => Shape::String(size),
+        (typ, 0, _) => Shape::Scalar(typ),
+        ("opaque", size, true) => Shape::FixedOpaque(size),
+        ("opaque", size, false) => Shape::VarOpaque(size),
+        (typ, size, true) => Shape::FixedArray(typ, size),
+        (typ, size, false) => Shape::VarArray(typ, size),
+    }
+}
+
+fn primitive(name: &str) -> Option<Primitive> {
+    match name {
+        "boolean" => Some(Primitive::Bool),
+        "int" => Some(Primitive::Int),
+        "unsigned int" => Some(Primitive::UInt),
+        "hyper" => Some(Primitive::Hyper),
+        "unsigned hyper" => Some(Primitive::UHyper),
+        "float" => Some(Primitive::Float),
+        "double" => Some(Primitive::Double),
+        _ => None,
+    }
+}
+
+fn pad(len: usize) -> usize {
+    (4 - len % 4) % 4
+}
+
+fn mismatch(path: &str, expected: &str) -> String {
+    format!("{}: expected {}", path, expected)
+}
+
+/// Deterministic xorshift generator for sample values.
+struct Rng(u64);
+
+impl Rng {
+    /// Seeds the generator through splitmix64 so nearby seeds give unrelated
+    /// sequences. Xorshift needs a non-zero state.
+    fn new(seed: u64) -> Rng {
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        match z ^ (z >> 31) {
+            0 => Rng(0x9e37_79b9_7f4a_7c15),
+            state => Rng(state),
+        }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    /// Picks a length up to `max`, keeping samples small and cutting off
+    /// recursion.
+    fn len(&mut self, max: u32, depth: usize) -> usize {
+        if depth > 3 {
+            return 0;
+        }
+        self.below(u64::from(max.min(8)) + 1) as usize
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize, path: &str) -> Result<&'a [u8], String> {
+        if self.data.len() - self.pos < len {
+            return Err(format!("{}: unexpected end of input at byte {}", path, self.pos));
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self, path: &str) -> Result<u32, String> {
+        let bytes = self.take(4, path)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn i32(&mut self, path: &str) -> Result<i32, String> {
+        self.u32(path).map(|val| val as i32)
+    }
+
+    fn u64(&mut self, path: &str) -> Result<u64, String> {
+        Ok(u64::from(self.u32(path)?) << 32 | u64::from(self.u32(path)?))
+    }
+
+    fn len(&mut self, max: u32, path: &str) -> Result<usize, String> {
+        match self.u32(path)? {
+            len if len > max => Err(format!("{}: length {} exceeds the maximum of {}", path, len, max)),
+            len => Ok(len as usize),
+        }
+    }
+
+    fn bytes(&mut self, size: u32, fixed: bool, path: &str) -> Result<Vec<u8>, String> {
+        let len = match fixed {
+            true => size as usize,
+            false => self.len(size, path)?,
+        };
+        let bytes = self.take(len, path)?.to_vec();
+        self.take(pad(len), path)?;
+        Ok(bytes)
+    }
+}
+
+/// Type lookup over a set of namespaces.
+pub struct Schema<'a> {
+    namespaces: &'a [Namespace],
+    types: HashMap<&'a str, Type<'a>>,
+}
+
+impl<'a> Schema<'a> {
+    pub fn new(namespaces: &'a [Namespace]) -> Self {
+        let mut types = HashMap::new();
+        for ns in namespaces {
+            for td in &ns.typedefs {
+                types.insert(td.def.name.as_str(), Type::Typedef(td));
+            }
+            for st in &ns.structs {
+                types.insert(st.name.as_str(), Type::Struct(st));
+            }
+            for en in &ns.enums {
+                types.insert(en.name.as_str(), Type::Enum(en));
+            }
+            for un in &ns.unions {
+                types.insert(un.name.as_str(), Type::Union(un));
+            }
+        }
+        Schema { namespaces, types }
+    }
+
+    /// Names of all user defined types, with the namespace they belong to.
+    pub fn type_names(&self) -> Vec<(&'a str, &'a str)> {
+        let mut names = Vec::new();
+        for ns in self.namespaces {
+            let defs = ns.typedefs.iter().map(|td| &td.def.name);
+            let structs = ns.structs.iter().map(|st| &st.name);
+            let enums = ns.enums.iter().map(|en| &en.name);
+            let unions = ns.unions.iter().map(|un| &un.name);
+            for name in defs.chain(structs).chain(enums).chain(unions) {
+                names.push((ns.name.as_str(), name.as_str()));
+            }
+        }
+        names
+    }
+
+    /// Resolves a type name, optionally qualified by its namespace as in
+    /// `mazzaroth.Transaction`.
+    fn lookup(&self, name: &str) -> Result<Type<'a>, String> {
+        if let Some(prim) = primitive(name) {
+            return Ok(Type::Primitive(prim));
+        }
+        let unqualified = match name.split_once('.') {
+            Some((ns, name)) if self.namespaces.iter().any(|n| n.name == ns) => name,
+            _ => name,
+        };
+        self.types
+            .get(unqualified)
+            .copied()
+            .ok_or_else(|| format!("unknown type {}", name))
+    }
+
+    fn discriminant(&self, un: &Union, case: &str) -> Result<i32, String> {
+        match self.lookup(&un.switch.enum_type)? {
+            Type::Enum(en) => en
+                .values
+                .iter()
+                .find(|v| v.name == case)
+                .map(|v| v.index)
+                .ok_or_else(|| format!("{}: unknown case {}", un.name, case)),
+            _ => case
+                .parse()
+                .map_err(|_| format!("{}: case {} is not an integer", un.name, case)),
+        }
+    }
+
+    fn case<'u>(&self, un: &'u Union, name: &str) -> Result<&'u Case, String> {
+        un.switch
+            .cases
+            .iter()
+            .find(|c| c.value == name)
+            .ok_or_else(|| format!("{}: unknown case {}", un.name, name))
+    }
+
+    /// Encodes `value` as the named type.
+    pub fn encode(&self, root: &str, value: &Value) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        self.encode_type(root, value, root, &mut out)?;
+        Ok(out)
+    }
+
+    fn encode_type(&self, name: &str, value: &Value, path: &str, out: &mut Vec<u8>) -> Result<(), String> {
+        match (self.lookup(name)?, value) {
+            (Type::Primitive(prim), value) => encode_primitive(prim, value, path, out),
+            (Type::Typedef(td), value) => self.encode_def(&td.def, value, path, out),
+            (Type::Struct(st), Value::Struct(fields)) => {
+                for prop in &st.props {
+                    let field = fields
+                        .iter()
+                        .find(|(name, _)| *name == prop.name)
+                        .map(|(_, v)| v)
+                        .ok_or_else(|| format!("{}: missing field {}", path, prop.name))?;
+                    self.encode_def(prop, field, &format!("{}.{}", path, prop.name), out)?;
+                }
+                Ok(())
+            }
+            (Type::Enum(en), Value::Enum(val)) => {
+                let index = en
+                    .values
+                    .iter()
+                    .find(|v| v.name == *val)
+                    .map(|v| v.index)
+                    .ok_or_else(|| format!("{}: unknown {} value {}", path, en.name, val))?;
+                out.extend_from_slice(&index.to_be_bytes());
+                Ok(())
+            }
+            (Type::Union(un), Value::Union(case, _, arm)) => {
+                let kind = self.discriminant(un, case)?;
+                out.extend_from_slice(&kind.to_be_bytes());
+                let case = self.case(un, case)?;
+                self.encode_def(&case.ret_type, arm, &format!("{}.{}", path, case.value), out)
+            }
+            (Type::Struct(_), _) => Err(mismatch(path, "a struct")),
+            (Type::Enum(_), _) => Err(mismatch(path, "an enum value")),
+            (Type::Union(_), _) => Err(mismatch(path, "a union")),
+        }
+    }
+
+    fn encode_def(&self, def: &Def, value: &Value, path: &str, out: &mut Vec<u8>) -> Result<(), String> {
+        match (shape(def), value) {
+            (Shape::Void, Value::Void) => Ok(()),
+            (Shape::Scalar(typ), value) => self.encode_type(typ, value, path, out),
+            (Shape::String(max), Value::String(val)) => encode_bytes(val.as_bytes(), max, false, path, out),
+            (Shape::FixedOpaque(size), Value::Opaque(val)) => encode_bytes(val, size, true, path, out),
+            (Shape::VarOpaque(max), Value::Opaque(val)) => encode_bytes(val, max, false, path, out),
+            (Shape::FixedArray(typ, size), Value::Array(items)) => {
+                if items.len() != size as usize {
+                    return Err(format!("{}: expected {} items, got {}", path, size, items.len()));
+                }
+                for (i, item) in items.iter().enumerate() {
+                    self.encode_type(typ, item, &format!("{}[{}]", path, i), out)?;
+                }
+                Ok(())
+            }
+            (Shape::VarArray(typ, max), Value::Array(items)) => {
+                if items.len() > max as usize {
+                    return Err(format!("{}: {} items exceed the maximum of {}", path, items.len(), max));
+                }
+                out.extend_from_slice(&(items.len() as u32).to_be_bytes());
+                for (i, item) in items.iter().enumerate() {
+                    self.encode_type(typ, item, &format!("{}[{}]", path, i), out)?;
+                }
+                Ok(())
+            }
+            (Shape::Void, _) => Err(mismatch(path, "void")),
+            (Shape::String(_), _) => Err(mismatch(path, "a string")),
+            (Shape::FixedOpaque(_), _) | (Shape::VarOpaque(_), _) => Err(mismatch(path, "opaque bytes")),
+            (Shape::FixedArray(..), _) | (Shape::VarArray(..), _) => Err(mismatch(path, "an array")),
+        }
+    }
+
+    /// Decodes `data` as the named type, requiring every byte to be consumed.
+    pub fn decode(&self, root: &str, data: &[u8]) -> Result<Value, String> {
+        let mut reader = Reader { data, pos: 0 };
+        let value = self.decode_type(root, &mut reader, root)?;
+        match data.len() - reader.pos {
+            0 => Ok(value),
+            n => Err(format!("{}: {} trailing bytes", root, n)),
+        }
+    }
+
+    fn decode_type(&self, name: &str, reader: &mut Reader, path: &str) -> Result<Value, String> {
+        match self.lookup(name)? {
+            Type::Primitive(prim) => decode_primitive(prim, reader, path),
+            Type::Typedef(td) => self.decode_def(&td.def, reader, path),
+            Type::Struct(st) => st
+                .props
+                .iter()
+                .map(|prop| {
+                    let value = self.decode_def(prop, reader, &format!("{}.{}", path, prop.name))?;
+                    Ok((prop.name.clone(), value))
+                })
+                .collect::<Result<_, String>>()
+                .map(Value::Struct),
+            Type::Enum(en) => {
+                let index = reader.i32(path)?;
+                en.values
+                    .iter()
+                    .find(|v| v.index == index)
+                    .map(|v| Value::Enum(v.name.clone()))
+                    .ok_or_else(|| format!("{}: invalid {} value {}", path, en.name, index))
+            }
+            Type::Union(un) => {
+                let kind = reader.i32(path)?;
+                let mut cases = un.switch.cases.iter();
+                let case = loop {
+                    match cases.next() {
+                        Some(case) if self.discriminant(un, &case.value)? == kind => break case,
+                        Some(_) => {}
+                        None => return Err(format!("{}: invalid {} discriminant {}", path, un.name, kind)),
+                    }
+                };
+                let arm = self.decode_def(&case.ret_type, reader, &format!("{}.{}", path, case.value))?;
+                Ok(Value::Union(case.value.clone(), case.ret_type.name.clone(), Box::new(arm)))
+            }
+        }
+    }
+
+    fn decode_def(&self, def: &Def, reader: &mut Reader, path: &str) -> Result<Value, String> {
+        match shape(def) {
+            Shape::Void => Ok(Value::Void),
+            Shape::Scalar(typ) => self.decode_type(typ, reader, path),
+            Shape::String(max) => {
+                let bytes = reader.bytes(max, false, path)?;
+                String::from_utf8(bytes)
+                    .map(Value::String)
+                    .map_err(|_| format!("{}: string is not valid UTF-8", path))
+            }
+            Shape::FixedOpaque(size) => reader.bytes(size, true, path).map(Value::Opaque),
+            Shape::VarOpaque(max) => reader.bytes(max, false, path).map(Value::Opaque),
+            Shape::FixedArray(typ, size) => (0..size as usize)
+                .map(|i| self.decode_type(typ, reader, &format!("{}[{}]", path, i)))
+                .collect::<Result<_, String>>()
+                .map(Value::Array),
+            Shape::VarArray(typ, max) => {
+                let len = reader.len(max, path)?;
+                (0..len)
+                    .map(|i| self.decode_type(typ, reader, &format!("{}[{}]", path, i)))
+                    .collect::<Result<_, String>>()
+                    .map(Value::Array)
+            }
+        }
+    }
+
+    /// Reads a value of the named type from JSON, accepting the shape
+    /// produced by `to_json` as well as enum and union case names in place
+    /// of their integer values. Bounds are checked so the result encodes.
+    pub fn read_json(&self, root: &str, json: &serde_json::Value) -> Result<Value, String> {
+        let value = self.read_type_json(root, json, root)?;
+        self.encode(root, &value)?;
+        Ok(value)
+    }
+
+    fn read_type_json(&self, name: &str, json: &serde_json::Value, path: &str) -> Result<Value, String> {
+        use serde_json::Value as Json;
+        match (self.lookup(name)?, json) {
+            (Type::Primitive(prim), json) => read_primitive_json(prim, json, path),
+            (Type::Typedef(td), json) => self.read_def_json(&td.def, json, path),
+            (Type::Struct(st), Json::Object(obj)) => {
+                let field_names: Vec<String> = st.props.iter().map(|prop| to_first_lower(&prop.name)).collect();
+                if let Some(key) = obj.keys().find(|key| {
+                    !st.props.iter().zip(&field_names).any(|(prop, json_name)| *key == json_name || **key == prop.name)
+                }) {
+                    return Err(format!("{}: unknown field {}", path, key));
+                }
+                st.props
+                    .iter()
+                    .zip(&field_names)
+                    .map(|(prop, json_name)| {
+                        let field = obj
+                            .get(json_name)
+                            .or_else(|| obj.get(&prop.name))
+                            .ok_or_else(|| format!("{}: missing field {}", path, json_name))?;
+                        let value = self.read_def_json(prop, field, &format!("{}.{}", path, prop.name))?;
+                        Ok((prop.name.clone(), value))
+                    })
+                    .collect::<Result<_, String>>()
+                    .map(Value::Struct)
+            }
+            (Type::Enum(en), Json::String(val)) => en
+                .values
+                .iter()
+                .find(|v| v.name == *val)
+                .map(|v| Value::Enum(v.name.clone()))
+                .ok_or_else(|| format!("{}: unknown {} value {}", path, en.name, val)),
+            (Type::Enum(en), Json::Number(val)) => en
+                .values
+                .iter()
+                .find(|v| val.as_i64() == Some(i64::from(v.index)))
+                .map(|v| Value::Enum(v.name.clone()))
+                .ok_or_else(|| format!("{}: invalid {} value {}", path, en.name, val)),
+            (Type::Union(un), Json::Object(obj)) => {
+                let kind = obj.get("type").ok_or_else(|| format!("{}: missing field type", path))?;
+                let case = match kind {
+                    Json::String(name) => self.case(un, name)?,
+                    Json::Number(n) => {
+                        let mut found = None;
+                        for case in &un.switch.cases {
+                            if n.as_i64() == Some(i64::from(self.discriminant(un, &case.value)?)) {
+                                found = Some(case);
+                                break;
+                            }
+                        }
+                        found.ok_or_else(|| format!("{}: invalid {} discriminant {}", path, un.name, n))?
+                    }
+                    _ => return Err(mismatch(&format!("{}.type", path), "a case name or discriminant")),
+                };
+                let arm_path = format!("{}.{}", path, case.value);
+                let arm = match shape(&case.ret_type) {
+                    Shape::Void => Value::Void,
+                    _ => {
+                        let data = obj
+                            .get("data")
+                            .or_else(|| obj.get(&to_first_lower(&case.ret_type.name)))
+                            .ok_or_else(|| format!("{}: missing field data", path))?;
+                        self.read_def_json(&case.ret_type, data, &arm_path)?
+                    }
+                };
+                Ok(Value::Union(case.value.clone(), case.ret_type.name.clone(), Box::new(arm)))
+            }
+            (Type::Struct(_), _) => Err(mismatch(path, "an object")),
+            (Type::Enum(_), _) => Err(mismatch(path, "an enum name or value")),
+            (Type::Union(_), _) => Err(mismatch(path, "an object with a type field")),
+        }
+    }
+
+    fn read_def_json(&self, def: &Def, json: &serde_json::Value, path: &str) -> Result<Value, String> {
+        use serde_json::Value as Json;
+        match (shape(def), json) {
+            (Shape::Void, _) => Ok(Value::Void),
+            (Shape::Scalar(typ), json) => self.read_type_json(typ, json, path),
+            (Shape::String(_), Json::String(val)) => Ok(Value::String(val.clone())),
+            (Shape::FixedOpaque(_), json) | (Shape::VarOpaque(_), json) => read_bytes_json(json, path).map(Value::Opaque),
+            (Shape::FixedArray(typ, _), Json::Array(items)) | (Shape::VarArray(typ, _), Json::Array(items)) => items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| self.read_type_json(typ, item, &format!("{}[{}]", path, i)))
+                .collect::<Result<_, String>>()
+                .map(Value::Array),
+            (Shape::String(_), _) => Err(mismatch(path, "a string")),
+            (Shape::FixedArray(..), _) | (Shape::VarArray(..), _) => Err(mismatch(path, "an array")),
+        }
+    }
+
+    /// Builds a deterministic sample value of the named type from `seed`.
+    pub fn sample(&self, root: &str, seed: u64) -> Result<Value, String> {
+        let mut rng = Rng::new(seed);
+        self.sample_type(root, &mut rng, 0)
+    }
+
+    fn sample_type(&self, name: &str, rng: &mut Rng, depth: usize) -> Result<Value, String> {
+        Ok(match self.lookup(name)? {
+            Type::Primitive(prim) => sample_primitive(prim, rng),
+            Type::Typedef(td) => self.sample_def(&td.def, rng, depth)?,
+            Type::Struct(st) => Value::Struct(
+                st.props
+                    .iter()
+                    .map(|prop| Ok((prop.name.clone(), self.sample_def(prop, rng, depth + 1)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            Type::Enum(en) => {
+                let val = &en.values[rng.below(en.values.len() as u64) as usize];
+                Value::Enum(val.name.clone())
+            }
+            Type::Union(un) => {
+                let case = &un.switch.cases[rng.below(un.switch.cases.len() as u64) as usize];
+                let arm = self.sample_def(&case.ret_type, rng, depth + 1)?;
+                Value::Union(case.value.clone(), case.ret_type.name.clone(), Box::new(arm))
+            }
+        })
+    }
+
+    fn sample_def(&self, def: &Def, rng: &mut Rng, depth: usize) -> Result<Value, String> {
+        let items = |len: usize, typ: &str, rng: &mut Rng| {
+            (0..len)
+                .map(|_| self.sample_type(typ, rng, depth + 1))
+                .collect::<Result<Vec<_>, String>>()
+                .map(Value::Array)
+        };
+        match shape(def) {
+            Shape::Void => Ok(Value::Void),
+            Shape::Scalar(typ) => self.sample_type(typ, rng, depth),
+            Shape::String(max) => {
+                let len = rng.len(max, depth);
+                Ok(Value::String((0..len).map(|_| char::from(b'a' + rng.below(26) as u8)).collect()))
+            }
+            Shape::FixedOpaque(size) => Ok(Value::Opaque((0..size).map(|_| rng.next() as u8).collect())),
+            Shape::VarOpaque(max) => {
+                let len = rng.len(max, depth);
+                Ok(Value::Opaque((0..len).map(|_| rng.next() as u8).collect()))
+            }
+            Shape::FixedArray(typ, size) => items(size as usize, typ, rng),
+            Shape::VarArray(typ, max) => {
+                let len = rng.len(max, depth);
+                items(len, typ, rng)
+            }
+        }
+    }
+
+    /// Renders `value` of the named type as JSON in the shape produced by the
+    /// generated Go code and the Rust serde impls, which write 64 bit struct
+    /// fields as strings.
+    pub fn to_json(&self, root: &str, value: &Value) -> Result<serde_json::Value, String> {
+        self.type_json(root, value, root)
+    }
+
+    fn type_json(&self, name: &str, value: &Value, path: &str) -> Result<serde_json::Value, String> {
+        use serde_json::Value as Json;
+        match (self.lookup(name)?, value) {
+            (Type::Primitive(_), value) => primitive_json(value, path),
+            (Type::Typedef(td), value) => self.def_json(&td.def, value, path),
+            (Type::Struct(st), Value::Struct(fields)) => {
+                let mut obj = serde_json::Map::new();
+                for prop in &st.props {
+                    let field = fields
+                        .iter()
+                        .find(|(name, _)| *name == prop.name)
+                        .map(|(_, v)| v)
+                        .ok_or_else(|| format!("{}: missing field {}", path, prop.name))?;
+                    // Matches the `,string` tag Go puts on int64 and uint64
+                    // fields, which typedefs of them do not get.
+                    let bignum = prop.array_size == 0 && (prop.type_name == "hyper" || prop.type_name == "unsigned hyper");
+                    let json = match field {
+                        Value::Hyper(val) if bignum => Json::from(val.to_string()),
+                        Value::UHyper(val) if bignum => Json::from(val.to_string()),
+                        field => self.def_json(prop, field, &format!("{}.{}", path, prop.name))?,
+                    };
+                    obj.insert(to_first_lower(&prop.name), json);
+                }
+                Ok(Json::Object(obj))
+            }
+            (Type::Enum(en), Value::Enum(val)) => en
+                .values
+                .iter()
+                .find(|v| v.name == *val)
+                .map(|v| Json::from(v.index))
+                .ok_or_else(|| format!("{}: unknown {} value {}", path, en.name, val)),
+            (Type::Union(un), Value::Union(case, _, arm)) => {
+                let kind = self.discriminant(un, case)?;
+                let case = self.case(un, case)?;
+                let data = match arm.as_ref() {
+                    Value::Void => Json::from(""),
+                    arm => self.def_json(&case.ret_type, arm, &format!("{}.{}", path, case.value))?,
+                };
+                let mut obj = serde_json::Map::new();
+                obj.insert(String::from("type"), Json::from(kind));
+                obj.insert(String::from("data"), data);
+                Ok(Json::Object(obj))
+            }
+            (Type::Struct(_), _) => Err(mismatch(path, "a struct")),
+            (Type::Enum(_), _) => Err(mismatch(path, "an enum value")),
+            (Type::Union(_), _) => Err(mismatch(path, "a union")),
+        }
+    }
+
+    fn def_json(&self, def: &Def, value: &Value, path: &str) -> Result<serde_json::Value, String> {
+        use serde_json::Value as Json;
+        match (shape(def), value) {
+            (Shape::Void, Value::Void) => Ok(Json::Null),
+            (Shape::Scalar(typ), value) => self.type_json(typ, value, path),
+            (Shape::String(_), Value::String(val)) => Ok(Json::from(val.as_str())),
+            // Go encodes fixed byte arrays as a list of numbers and slices as base64.
+            (Shape::FixedOpaque(_), Value::Opaque(val)) => Ok(Json::from(val.clone())),
+            (Shape::VarOpaque(_), Value::Opaque(val)) => Ok(Json::from(base64_encode(val))),
+            (Shape::FixedArray(typ, _), Value::Array(items)) | (Shape::VarArray(typ, _), Value::Array(items)) => items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| self.type_json(typ, item, &format!("{}[{}]", path, i)))
+                .collect::<Result<Vec<_>, String>>()
+                .map(Json::Array),
+            (Shape::Void, _) => Err(mismatch(path, "void")),
+            (Shape::String(_), _) => Err(mismatch(path, "a string")),
+            (Shape::FixedOpaque(_), _) | (Shape::VarOpaque(_), _) => Err(mismatch(path, "opaque bytes")),
+            (Shape::FixedArray(..), _) | (Shape::VarArray(..), _) => Err(mismatch(path, "an array")),
+        }
+    }
+}
+
+fn encode_primitive(prim: Primitive, value: &Value, path: &str, out: &mut Vec<u8>) -> Result<(), String> {
+    match (prim, value) {
+        (Primitive::Bool, Value::Bool(val)) => out.extend_from_slice(&(*val as u32).to_be_bytes()),
+        (Primitive::Int, Value::Int(val)) => out.extend_from_slice(&val.to_be_bytes()),
+        (Primitive::UInt, Value::UInt(val)) => out.extend_from_slice(&val.to_be_bytes()),
+        (Primitive::Hyper, Value::Hyper(val)) => out.extend_from_slice(&val.to_be_bytes()),
+        (Primitive::UHyper, Value::UHyper(val)) => out.extend_from_slice(&val.to_be_bytes()),
+        (Primitive::Float, Value::Float(val)) => out.extend_from_slice(&val.to_bits().to_be_bytes()),
+        (Primitive::Double, Value::Double(val)) => out.extend_from_slice(&val.to_bits().to_be_bytes()),
+        (prim, _) => return Err(mismatch(path, &format!("{:?}", prim).to_lowercase())),
+    }
+    Ok(())
+}
+
+fn encode_bytes(val: &[u8], size: u32, fixed: bool, path: &str, out: &mut Vec<u8>) -> Result<(), String> {
+    match fixed {
+        true if val.len() != size as usize => {
+            return Err(format!("{}: expected {} bytes, got {}", path, size, val.len()));
+        }
+        false if val.len() > size as usize => {
+            return Err(format!("{}: {} bytes exceed the maximum of {}", path, val.len(), size));
+        }
+        true => {}
+        false => out.extend_from_slice(&(val.len() as u32).to_be_bytes()),
+    }
+    out.extend_from_slice(val);
+    out.resize(out.len() + pad(val.len()), 0);
+    Ok(())
+}
+
+fn decode_primitive(prim: Primitive, reader: &mut Reader, path: &str) -> Result<Value, String> {
+    Ok(match prim {
+        Primitive::Bool => match reader.u32(path)? {
+            0 => Value::Bool(false),
+            1 => Value::Bool(true),
+            val => return Err(format!("{}: invalid boolean {}", path, val)),
+        },
+        Primitive::Int => Value::Int(reader.i32(path)?),
+        Primitive::UInt => Value::UInt(reader.u32(path)?),
+        Primitive::Hyper => Value::Hyper(reader.u64(path)? as i64),
+        Primitive::UHyper => Value::UHyper(reader.u64(path)?),
+        Primitive::Float => Value::Float(f32::from_bits(reader.u32(path)?)),
+        Primitive::Double => Value::Double(f64::from_bits(reader.u64(path)?)),
+    })
+}
+
+fn read_primitive_json(prim: Primitive, json: &serde_json::Value, path: &str) -> Result<Value, String> {
+    use serde_json::Value as Json;
+    let int = |json: &Json| match json {
+        Json::Number(n) => n.as_i64().map(i128::from).or_else(|| n.as_u64().map(i128::from)),
+        Json::String(s) => s.parse().ok(),
+        _ => None,
+    };
+    let ranged = |lo: i128, hi: i128| {
+        int(json)
+            .filter(|n| (lo..=hi).contains(n))
+            .ok_or_else(|| mismatch(path, &format!("an integer from {} to {}", lo, hi)))
+    };
+    Ok(match prim {
+        Primitive::Bool => Value::Bool(json.as_bool().ok_or_else(|| mismatch(path, "a boolean"))?),
+        Primitive::Int => Value::Int(ranged(i32::MIN.into(), i32::MAX.into())? as i32),
+        Primitive::UInt => Value::UInt(ranged(0, u32::MAX.into())? as u32),
+        Primitive::Hyper => Value::Hyper(ranged(i64::MIN.into(), i64::MAX.into())? as i64),
+        Primitive::UHyper => Value::UHyper(ranged(0, u64::MAX.into())? as u64),
+        Primitive::Float => Value::Float(json.as_f64().ok_or_else(|| mismatch(path, "a number"))? as f32),
+        Primitive::Double => Value::Double(json.as_f64().ok_or_else(|| mismatch(path, "a number"))?),
+    })
+}
+
+/// Opaque data is a list of byte values, a `0x` prefixed hex string or a
+/// base64 string.
+fn read_bytes_json(json: &serde_json::Value, path: &str) -> Result<Vec<u8>, String> {
+    use serde_json::Value as Json;
+    let bytes = match json {
+        Json::Array(items) => items
+            .iter()
+            .map(|item| item.as_u64().filter(|b| *b <= 255).map(|b| b as u8))
+            .collect::<Option<_>>(),
+        Json::String(val) if val.starts_with("0x") => hex_decode(val),
+        Json::String(val) => base64_decode(val),
+        _ => None,
+    };
+    bytes.ok_or_else(|| mismatch(path, "bytes as a list, 0x prefixed hex or base64"))
+}
+
+fn sample_primitive(prim: Primitive, rng: &mut Rng) -> Value {
+    match prim {
+        Primitive::Bool => Value::Bool(rng.below(2) == 1),
+        Primitive::Int => Value::Int(rng.next() as i32),
+        Primitive::UInt => Value::UInt(rng.next() as u32),
+        Primitive::Hyper => Value::Hyper(rng.next() as i64),
+        Primitive::UHyper => Value::UHyper(rng.next()),
+        // Multiples of 1/1024 print and parse back exactly in every language.
+        Primitive::Float => Value::Float(rng.below(1 << 20) as f32 / 1024.0),
+        Primitive::Double => Value::Double(rng.below(1 << 40) as f64 / 1024.0),
+    }
+}
+
+fn primitive_json(value: &Value, path: &str) -> Result<serde_json::Value, String> {
+    use serde_json::Value as Json;
+    match value {
+        Value::Bool(val) => Ok(Json::from(*val)),
+        Value::Int(val) => Ok(Json::from(*val)),
+        Value::UInt(val) => Ok(Json::from(*val)),
+        Value::Hyper(val) => Ok(Json::from(*val)),
+        Value::UHyper(val) => Ok(Json::from(*val)),
+        Value::Float(val) => Ok(Json::from(f64::from(*val))),
+        Value::Double(val) => Ok(Json::from(*val)),
+        _ => Err(mismatch(path, "a primitive")),
+    }
+}
+
+static BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().fold(0u32, |acc, b| acc << 8 | u32::from(*b)) << (8 * (3 - chunk.len()));
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => out.push(char::from(BASE64[(n >> (18 - 6 * i) & 63) as usize])),
+                false => out.push('='),
+            }
+        }
+    }
+    out
+}
+
+pub fn base64_decode(value: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u32> = value
+        .trim_end_matches('=')
+        .bytes()
+        .filter(|b| !b.is_ascii_whitespace())
+        .map(|b| BASE64.iter().position(|&c| c == b).map(|n| n as u32))
+        .collect::<Option<_>>()?;
+    let mut out = Vec::new();
+    for chunk in digits.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let n = chunk.iter().fold(0, |acc, d| acc << 6 | d) << (6 * (4 - chunk.len()));
+        for i in 0..chunk.len() - 1 {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(out)
+}
+
+pub fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn hex_decode(value: &str) -> Option<Vec<u8>> {
+    let value: String = value.trim_start_matches("0x").chars().filter(|c| !c.is_whitespace()).collect();
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment<'p> {
+    Name(&'p str),
+    Index(usize),
+}
+
+/// Splits `action.category.call.function` or `address[0]` style paths.
+fn segments(path: &str) -> Option<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+    for part in path.split('.').filter(|part| !part.is_empty()) {
+        let mut pieces = part.split('[');
+        match pieces.next()? {
+            "" => {}
+            name if name.bytes().all(|b| b.is_ascii_digit()) => segments.push(Segment::Index(name.parse().ok()?)),
+            name => segments.push(Segment::Name(name)),
+        }
+        for index in pieces {
+            segments.push(Segment::Index(index.strip_suffix(']')?.parse().ok()?));
+        }
+    }
+    Some(segments)
+}
+
+/// Whether a path segment names a union arm: its arm name, as declared or
+/// with a lower case first letter, or else its case name in any ASCII case.
+fn arm_matches(case: &str, arm: &str, name: &str) -> bool {
+    match arm.is_empty() {
+        false if arm == name || to_first_lower(arm) == name => true,
+        _ => case.eq_ignore_ascii_case(name),
+    }
+}
+
+impl Value {
+    /// Looks up a nested value by path. Struct fields are matched by name,
+    /// array items by `[i]` or `.i` and union arms by their name, falling
+    /// back to the case name ignoring ASCII case, so both
+    /// `action.category.call.function` and `action.category.CALL.function`
+    /// reach the function of a `CALL` category with a `call` arm.
+    pub fn get(&self, path: &str) -> Option<&Value> {
+        segments(path)?.into_iter().try_fold(self, |value, segment| match (value, segment) {
+            (Value::Struct(fields), Segment::Name(name)) => fields
+                .iter()
+                .find(|(field, _)| field == name || to_first_lower(field) == name)
+                .map(|(_, v)| v),
+            (Value::Array(items), Segment::Index(i)) => items.get(i),
+            (Value::Union(case, arm_name, arm), Segment::Name(name)) if arm_matches(case, arm_name, name) => Some(arm.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// Mutable counterpart of `get`.
+    pub fn get_mut(&mut self, path: &str) -> Option<&mut Value> {
+        segments(path)?.into_iter().try_fold(self, |value, segment| match (value, segment) {
+            (Value::Struct(fields), Segment::Name(name)) => fields
+                .iter_mut()
+                .find(|(field, _)| field == name || to_first_lower(field) == name)
+                .map(|(_, v)| v),
+            (Value::Array(items), Segment::Index(i)) => items.get_mut(i),
+            (Value::Union(case, arm_name, arm), Segment::Name(name)) if arm_matches(case, arm_name, name) => Some(arm.as_mut()),
+            _ => None,
+        })
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(val) => Some(*val),
+            _ => None,
+        }
+    }
+
+    /// Any integer value that fits in an `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Int(val) => Some(i64::from(*val)),
+            Value::UInt(val) => Some(i64::from(*val)),
+            Value::Hyper(val) => Some(*val),
+            Value::UHyper(val) => i64::try_from(*val).ok(),
+            _ => None,
+        }
+    }
+
+    /// Any non-negative integer value.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Int(val) => u64::try_from(*val).ok(),
+            Value::UInt(val) => Some(u64::from(*val)),
+            Value::Hyper(val) => u64::try_from(*val).ok(),
+            Value::UHyper(val) => Some(*val),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Float(val) => Some(f64::from(*val)),
+            Value::Double(val) => Some(*val),
+            _ => None,
+        }
+    }
+
+    /// String contents, or the name of an enum value or union case.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(val) | Value::Enum(val) | Value::Union(val, _, _) => Some(val),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Opaque(val) => Some(val),
+            _ => None,
+        }
+    }
+
+    /// Renders the value as an indented tree for reading in a terminal.
+    pub fn to_tree(&self) -> String {
+        let mut out = String::new();
+        self.write_tree(0, &mut out);
+        out
+    }
+
+    fn write_tree(&self, indent: usize, out: &mut String) {
+        let nested = |out: &mut String, label: &str, value: &Value| {
+            out.push_str(&format!("\n{}{}:", "  ".repeat(indent + 1), label));
+            value.write_tree(indent + 1, out);
+        };
+        match self {
+            Value::Void => out.push_str(" void"),
+            Value::Bool(val) => out.push_str(&format!(" {}", val)),
+            Value::Int(val) => out.push_str(&format!(" {}", val)),
+            Value::UInt(val) => out.push_str(&format!(" {}", val)),
+            Value::Hyper(val) => out.push_str(&format!(" {}", val)),
+            Value::UHyper(val) => out.push_str(&format!(" {}", val)),
+            Value::Float(val) => out.push_str(&format!(" {}", val)),
+            Value::Double(val) => out.push_str(&format!(" {}", val)),
+            Value::String(val) => out.push_str(&format!(" {:?}", val)),
+            Value::Opaque(val) => out.push_str(&format!(" 0x{}", hex_encode(val))),
+            Value::Enum(val) => out.push_str(&format!(" {}", val)),
+            Value::Array(items) => {
+                out.push_str(&format!(" [{}]", items.len()));
+                for (i, item) in items.iter().enumerate() {
+                    nested(out, &format!("[{}]", i), item);
+                }
+            }
+            Value::Struct(fields) => {
+                for (name, field) in fields {
+                    nested(out, name, field);
+                }
+            }
+            Value::Union(case, _, arm) => {
+                out.push_str(&format!(" {}", case));
+                if **arm != Value::Void {
+                    arm.write_tree(indent, out);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Vec<Namespace> {
+        build_namespaces(String::from(
+            "namespace test
+{
+    typedef opaque Hash[4];
+
+    enum Kind
+    {
+        A = 0,
+        B = 5
+    };
+
+    struct Item
+    {
+        Hash hash;
+        string name<8>;
+        unsigned hyper amount;
+        int values<2>;
+    };
+
+    union Body switch (Kind Type)
+    {
+        case A:
+            Item item;
+        case B:
+            void;
+    }
+}",
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn encode() {
+        let namespaces = schema();
+        let schema = Schema::new(&namespaces);
+        let item = Value::Struct(vec![
+            (String::from("hash"), Value::Opaque(vec![1, 2, 3, 4])),
+            (String::from("name"), Value::String(String::from("abcde"))),
+            (String::from("amount"), Value::UHyper(7)),
+            (String::from("values"), Value::Array(vec![Value::Int(-1)])),
+        ]);
+        let body = Value::Union(String::from("A"), String::from("item"), Box::new(item));
+        let encoded = schema.encode("test.Body", &body).unwrap();
+        assert_eq!(
+            hex_encode(&encoded),
+            "00000000010203040000000561626364650000000000000000000007\
+             00000001ffffffff"
+        );
+        let void = Value::Union(String::from("B"), String::new(), Box::new(Value::Void));
+        assert_eq!(hex_encode(&schema.encode("Body", &void).unwrap()), "00000005");
+    }
+
+    #[test]
+    fn encode_errors() {
+        let namespaces = schema();
+        let schema = Schema::new(&namespaces);
+        let err = schema.encode("Hash", &Value::Opaque(vec![1])).unwrap_err();
+        assert_eq!(err, "Hash: expected 4 bytes, got 1");
+        let err = schema.encode("Kind", &Value::Enum(String::from("C"))).unwrap_err();
+        assert_eq!(err, "Kind: unknown Kind value C");
+        assert!(schema.encode("Missing", &Value::Void).is_err());
+        assert_eq!(build_namespaces(String::from("namespace test {")).unwrap_err(), "unsuccessful parse");
+    }
+
+    #[test]
+    fn sample_and_json() {
+        let namespaces = schema();
+        let schema = Schema::new(&namespaces);
+        let sample = schema.sample("Item", 42).unwrap();
+        assert_eq!(sample, schema.sample("Item", 42).unwrap());
+        let encoded = schema.encode("Item", &sample).unwrap();
+        assert_eq!(encoded.len() % 4, 0);
+        let json = schema.to_json("Item", &sample).unwrap();
+        assert!(json["hash"].is_array());
+        assert!(json["amount"].is_string());
+        let void = Value::Union(String::from("B"), String::new(), Box::new(Value::Void));
+        assert_eq!(schema.to_json("Body", &void).unwrap().to_string(), r#"{"type":5,"data":""}"#);
+
+        let namespaces = build_namespaces(String::from(
+            "namespace test { typedef unsigned hyper Amount; struct Fee { Amount amount; hyper delta; }; }",
+        ))
+        .unwrap();
+        let schema = Schema::new(&namespaces);
+        let fee = Value::Struct(vec![
+            (String::from("amount"), Value::UHyper(7)),
+            (String::from("delta"), Value::Hyper(-1)),
+        ]);
+        assert_eq!(schema.to_json("Fee", &fee).unwrap().to_string(), r#"{"amount":7,"delta":"-1"}"#);
+    }
+
+    #[test]
+    fn decode() {
+        let namespaces = schema();
+        let schema = Schema::new(&namespaces);
+        for seed in 0..20 {
+            let sample = schema.sample("Body", seed).unwrap();
+            let encoded = schema.encode("Body", &sample).unwrap();
+            assert_eq!(schema.decode("Body", &encoded).unwrap(), sample);
+        }
+        let void = schema.decode("Body", &[0, 0, 0, 5]).unwrap();
+        assert_eq!(void, Value::Union(String::from("B"), String::new(), Box::new(Value::Void)));
+        assert_eq!(void.to_tree(), " B");
+        let err = schema.decode("Body", &[0, 0, 0, 1]).unwrap_err();
+        assert_eq!(err, "Body: invalid Body discriminant 1");
+        let err = schema.decode("Kind", &[0, 0, 0, 0, 0]).unwrap_err();
+        assert_eq!(err, "Kind: 1 trailing bytes");
+        let err = schema.decode("Item", &[1, 2, 3, 4, 0, 0, 0, 9]).unwrap_err();
+        assert_eq!(err, "Item.name: length 9 exceeds the maximum of 8");
+    }
+
+    #[test]
+    fn read_json() {
+        let namespaces = schema();
+        let schema = Schema::new(&namespaces);
+        for seed in 0..20 {
+            let sample = schema.sample("Body", seed).unwrap();
+            let json = schema.to_json("Body", &sample).unwrap();
+            assert_eq!(schema.read_json("Body", &json).unwrap(), sample);
+        }
+        let json = serde_json::json!({
+            "type": "A",
+            "data": {"hash": "0x01020304", "name": "abc", "amount": 7, "values": [1, -2]}
+        });
+        let value = schema.read_json("Body", &json).unwrap();
+        assert_eq!(hex_encode(&schema.encode("Body", &value).unwrap()).len(), 72);
+        let err = schema.read_json("Kind", &serde_json::json!("C")).unwrap_err();
+        assert_eq!(err, "Kind: unknown Kind value C");
+        let err = schema.read_json("Body", &serde_json::json!({"type": 1})).unwrap_err();
+        assert_eq!(err, "Body: invalid Body discriminant 1");
+        let json = serde_json::json!({"hash": [1, 2, 3, 4], "name": "abc", "amount": "1", "values": [1, 2, 3]});
+        let err = schema.read_json("Item", &json).unwrap_err();
+        assert_eq!(err, "Item.values: 3 items exceed the maximum of 2");
+        let json = serde_json::json!({"hash": [1, 2, 3, 4], "name": "abc", "amount": -1, "values": []});
+        let err = schema.read_json("Item", &json).unwrap_err();
+        assert_eq!(err, "Item.amount: expected an integer from 0 to 18446744073709551615");
+        let json = serde_json::json!({"hash": [1, 2, 3, 4], "name": "", "amount": 1, "values": [], "extra": 1});
+        assert_eq!(schema.read_json("Item", &json).unwrap_err(), "Item: unknown field extra");
+    }
+
+    #[test]
+    fn paths() {
+        let namespaces = schema();
+        let schema = Schema::new(&namespaces);
+        let item = Value::Struct(vec![
+            (String::from("hash"), Value::Opaque(vec![1, 2, 3, 4])),
+            (String::from("name"), Value::String(String::from("abc"))),
+            (String::from("amount"), Value::UHyper(7)),
+            (String::from("values"), Value::Array(vec![Value::Int(1), Value::Int(-2)])),
+        ]);
+        let mut body = Value::Union(String::from("A"), String::from("item"), Box::new(item));
+        assert_eq!(body.as_str(), Some("A"));
+        assert_eq!(body.get("item.name").and_then(Value::as_str), Some("abc"));
+        assert_eq!(body.get("a.name").and_then(Value::as_str), Some("abc"));
+        assert_eq!(body.get("a.values[1]").and_then(Value::as_i64), Some(-2));
+        assert_eq!(body.get("a.values.0").and_then(Value::as_u64), Some(1));
+        assert_eq!(body.get("a.hash").and_then(Value::as_bytes), Some(&[1, 2, 3, 4][..]));
+        assert_eq!(body.get("b"), None);
+        assert_eq!(body.get("a.values[2]"), None);
+        assert_eq!(body.get("b.name"), None);
+        *body.get_mut("item.amount").unwrap() = Value::UHyper(9);
+        let decoded = schema.decode("Body", &schema.encode("Body", &body).unwrap()).unwrap();
+        assert_eq!(decoded.get("A.amount").and_then(Value::as_u64), Some(9));
+        assert_eq!(segments("x[1][2].y"), Some(vec![Segment::Name("x"), Segment::Index(1), Segment::Index(2), Segment::Name("y")]));
+        assert_eq!(segments("x[a]"), None);
+    }
+
+    #[test]
+    fn tree() {
+        let item = Value::Struct(vec![
+            (String::from("hash"), Value::Opaque(vec![1, 2])),
+            (String::from("values"), Value::Array(vec![Value::Int(-1)])),
+        ]);
+        assert_eq!(item.to_tree(), "\n  hash: 0x0102\n  values: [1]\n    [0]: -1");
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64_decode("Zm8=").unwrap(), b"fo");
+        assert_eq!(base64_decode("Zm9vYmFy").unwrap(), b"foobar");
+        assert!(base64_decode("Z").is_none());
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(hex_decode("0x00ff10").unwrap(), vec![0, 255, 16]);
+        assert!(hex_decode("abc").is_none());
+        assert!(hex_decode("zz").is_none());
+    }
+}
@@ -0,0 +1,262 @@
+use super::ast::*;
+use super::value::{hex_encode, Schema};
+use handlebars::Handlebars;
+
+static GO_T: &str = r#"
+// Package {{package}} vector tests are automatically generated
+// DO NOT EDIT or your changes may be overwritten
+package {{package}}
+
+import (
+  "bytes"
+  "encoding"
+  "encoding/hex"
+  "testing"
+)
+
+type vectorValue interface {
+  encoding.BinaryMarshaler
+  encoding.BinaryUnmarshaler
+}
+
+var vectors = []struct {
+  name  string
+  value func() vectorValue
+  hex   string
+}{
+{{#each vectors as |v|}}
+  {"{{v.name}}", func() vectorValue { return new({{v.type}}) }, "{{v.hex}}"},
+{{/each}}
+}
+
+func TestVectors(t *testing.T) {
+  for _, v := range vectors {
+    data, err := hex.DecodeString(v.hex)
+    if err != nil {
+      t.Fatalf("%s: invalid hex: %v", v.name, err)
+    }
+    value := v.value()
+    if err := value.UnmarshalBinary(data); err != nil {
+      t.Fatalf("%s: decoding failed: %v", v.name, err)
+    }
+    encoded, err := value.MarshalBinary()
+    if err != nil {
+      t.Fatalf("%s: encoding failed: %v", v.name, err)
+    }
+    if !bytes.Equal(encoded, data) {
+      t.Errorf("%s: encoding mismatch: %x != %s", v.name, encoded, v.hex)
+    }
+  }
+}
+"#;
+
+static RUST_T: &str = r#"
+// Vector tests are automatically generated
+// DO NOT EDIT or your changes may be overwritten
+#[cfg(test)]
+mod vectors {
+    use super::*;
+
+    static VECTORS: &[(&str, &str, &str)] = &[
+{{#each vectors as |v|}}
+        ("{{v.name}}", "{{v.type}}", "{{v.hex}}"),
+{{/each}}
+    ];
+
+    fn hex_decode(val: &str) -> Vec<u8> {
+        (0..val.len()).step_by(2).map(|i| u8::from_str_radix(&val[i..i + 2], 16).unwrap()).collect()
+    }
+
+    fn check<T: XDRIn + XDROut>(name: &str, data: &[u8]) {
+        let (value, read) = T::read_xdr(data).unwrap_or_else(|e| panic!("{}: decoding failed: {:?}", name, e));
+        assert_eq!(read as usize, data.len(), "{}: trailing bytes", name);
+        let mut encoded = Vec::new();
+        value.write_xdr(&mut encoded).unwrap_or_else(|e| panic!("{}: encoding failed: {:?}", name, e));
+        assert_eq!(encoded, data, "{}: encoding mismatch", name);
+    }
+
+    #[test]
+    fn vectors() {
+        for (name, typ, hex) in VECTORS {
+            let data = hex_decode(hex);
+            match *typ {
+{{#each types as |typ|}}
+                "{{typ}}" => check::<{{typ}}>(name, &data),
+{{/each}}
+                _ => panic!("{}: unknown type {}", name, typ),
+            }
+        }
+    }
+}
+"#;
+
+static JS_T: &str = r#"
+// Vector tests are automatically generated
+// DO NOT EDIT or your changes may be overwritten
+import * as xdr from './{{module}}'
+
+const vectors = [
+{{#each vectors as |v|}}
+  ['{{v.name}}', '{{v.type}}', '{{v.hex}}'],
+{{/each}}
+]
+
+describe('vectors', () => {
+  vectors.forEach(([name, type, hex]) => {
+    test(name, () => {
+      const value = xdr[type]()
+      value.fromXDR(Buffer.from(hex, 'hex'))
+      expect(Buffer.from(value.toXDR()).toString('hex')).toBe(hex)
+    })
+  })
+})
+"#;
+
+/// A sample value of a schema type with its canonical encoding.
+#[derive(Debug, Serialize)]
+pub struct Vector {
+    pub name: String,
+
+    pub namespace: String,
+
+    #[serde(rename = "type")]
+    pub type_name: String,
+
+    pub hex: String,
+
+    pub json: serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct VectorFile<'a> {
+    version: u32,
+
+    vectors: &'a [Vector],
+}
+
+#[derive(Serialize)]
+struct RenderData<'a> {
+    vectors: &'a [Vector],
+
+    types: Vec<&'a str>,
+
+    package: &'a str,
+
+    module: &'a str,
+}
+
+fn fnv1a(val: &str) -> u64 {
+    val.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| (hash ^ u64::from(b)).wrapping_mul(0x100_0000_01b3))
+}
+
+/// Builds `count` deterministic vectors for every type in the namespaces. The
+/// seeds only depend on the vector name so adding types keeps existing vectors
+/// stable.
+pub fn build(namespaces: &[Namespace], count: u64) -> Result<Vec<Vector>, String> {
+    let schema = Schema::new(namespaces);
+    let mut vectors = Vec::new();
+    for (namespace, name) in schema.type_names() {
+        for i in 0..count {
+            let vector = format!("{}#{}", name, i);
+            let value = schema.sample(name, fnv1a(&vector))?;
+            vectors.push(Vector {
+                name: vector,
+                namespace: namespace.to_string(),
+                type_name: name.to_string(),
+                hex: hex_encode(&schema.encode(name, &value)?),
+                json: schema.to_json(name, &value)?,
+            });
+        }
+    }
+    Ok(vectors)
+}
+
+/// Serializes vectors to the versioned vectors file format.
+pub fn to_json(vectors: &[Vector]) -> String {
+    serde_json::to_string_pretty(&VectorFile { version: 1, vectors }).unwrap()
+}
+
+fn render(template: &str, vectors: &[Vector], package: &str, module: &str) -> String {
+    let mut reg = Handlebars::new();
+    reg.register_escape_fn(handlebars::no_escape);
+    let mut types: Vec<&str> = vectors.iter().map(|v| v.type_name.as_str()).collect();
+    types.dedup();
+    let data = RenderData {
+        vectors,
+        types,
+        package,
+        module,
+    };
+    reg.render_template(template, &data).unwrap()
+}
+
+/// Go test checking the generated code decodes and re-encodes every vector
+/// byte for byte.
+pub fn go_harness(vectors: &[Vector], package: &str) -> String {
+    render(GO_T, vectors, package, "")
+}
+
+/// Rust test module for appending to (or including in) the generated code.
+pub fn rust_harness(vectors: &[Vector]) -> String {
+    render(RUST_T, vectors, "", "")
+}
+
+/// Jest test importing the generated JavaScript from `module`.
+pub fn js_harness(vectors: &[Vector], module: &str) -> String {
+    render(JS_T, vectors, "", module)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn namespaces() -> Vec<Namespace> {
+        build_namespaces(String::from(
+            "namespace test
+{
+    typedef opaque Hash[4];
+
+    enum Kind
+    {
+        A = 0,
+        B = 1
+    };
+
+    struct Item
+    {
+        Hash hash;
+        Kind kind;
+    };
+}",
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn deterministic() {
+        let vectors = build(&namespaces(), 2).unwrap();
+        assert_eq!(vectors.len(), 6);
+        assert_eq!(vectors[0].name, "Hash#0");
+        assert_eq!(vectors[0].hex.len(), 8);
+        assert_eq!(to_json(&vectors), to_json(&build(&namespaces(), 2).unwrap()));
+        assert!(to_json(&vectors).contains("\"version\": 1"));
+        let vectors = build(&namespaces(), 8).unwrap();
+        for name in &["Hash", "Item"] {
+            let mut hex: Vec<&str> = vectors.iter().filter(|v| v.type_name == *name).map(|v| v.hex.as_str()).collect();
+            hex.sort_unstable();
+            hex.dedup();
+            assert_eq!(hex.len(), 8);
+        }
+    }
+
+    #[test]
+    fn harnesses() {
+        let vectors = build(&namespaces(), 1).unwrap();
+        let go = go_harness(&vectors, "xdr");
+        assert!(go.contains(&format!("{{\"Item#0\", func() vectorValue {{ return new(Item) }}, \"{}\"}},", vectors[1].hex)));
+        let rust = rust_harness(&vectors);
+        assert!(rust.contains("\"Item\" => check::<Item>(name, &data),"));
+        let js = js_harness(&vectors, "xdr");
+        assert!(js.contains(&format!("['Kind#0', 'Kind', '{}'],", vectors[2].hex)));
+    }
+}